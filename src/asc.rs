@@ -0,0 +1,167 @@
+//! Vector ASC log files abstractions, as used by CANalyzer and CANoe.
+
+use bxcan::{Data, ExtendedId, Frame, Id, StandardId};
+use heapless::Vec;
+use rtt_target::rprintln;
+
+use crate::sd::{parse_micros, BadLines, LogLines, LogSource};
+
+/// An iterator over CAN frames contained in a Vector ASC log file.
+///
+/// Lines look as follows, the time being in seconds since the start of the measurement :
+/// ```text
+///    0.001234 1  123             Rx   d 8 00 11 22 33 44 55 66 77
+///    0.002000 1  12345678x       Tx   d 2 AA BB
+///    0.003000 1  7DF             Rx   r 8
+/// ```
+///
+/// Identifiers ending with `x` are extended ones. Header lines, comments and events other than
+/// CAN frames (error frames, statistics...) are skipped, CAN FD frames (`CANFD` lines) with a
+/// warning. Numbers are hexadecimal unless the header contains `base dec`.
+///
+/// # To-Do
+/// - [ ] Support `timestamps relative` headers, times being read as absolute ones.
+/// - [ ] Keep the `base` of the header when seeking past it.
+pub struct AscLogsIterator<S: LogSource> {
+    /// The lines of the log file that will be scanned for CAN frames.
+    lines: LogLines<S>,
+    /// Time of the last frame read, in microseconds since the start of the measurement.
+    timestamp: u64,
+    /// Whether numbers are decimal (`base dec` header) rather than hexadecimal.
+    decimal: bool,
+}
+
+impl<S: LogSource> AscLogsIterator<S> {
+    /// Creates a new [`AscLogsIterator`] from a given [`LogSource`].
+    pub fn new(log_file: S) -> Self {
+        Self {
+            lines: LogLines::new(log_file),
+            timestamp: 0,
+            decimal: false,
+        }
+    }
+
+    /// Moves to the line starting `offset` bytes from the start of the log file, as returned by
+    /// [`offset()`][AscLogsIterator::offset()].
+    pub fn seek(&mut self, offset: u32) -> Result<(), ()> {
+        self.lines.seek(offset)
+    }
+
+    /// Returns the offset in bytes of the next line to be read in the log file.
+    pub fn offset(&self) -> u32 {
+        self.lines.offset()
+    }
+
+    /// Returns the time of the last frame read, in seconds.
+    pub fn timestamp(&self) -> u32 {
+        (self.timestamp / 1_000_000) as u32
+    }
+
+    /// Returns the time of the last frame read, in microseconds.
+    pub fn timestamp_micros(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns the lines skipped so far because they couldn't be parsed.
+    pub fn bad_lines(&self) -> &BadLines {
+        self.lines.bad_lines()
+    }
+
+    /// Returns a mutable reference to the [`LogSource`] being read.
+    pub fn source_mut(&mut self) -> &mut S {
+        self.lines.source_mut()
+    }
+}
+
+impl<S: LogSource> Iterator for AscLogsIterator<S> {
+    type Item = Frame;
+
+    /// Reads the next [`Frame`] from the log file.
+    ///
+    /// If any error is encountered while reading the file, [`None`] is returned and iteration ends.
+    /// Lines that can't be parsed are skipped, and recorded in [`bad_lines()`][Self::bad_lines()].
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(line) = self.lines.next_line() {
+            let mut fields = line.split_whitespace();
+            let Some(time) = fields.next() else {
+                continue;
+            };
+            if time == "base" {
+                self.decimal = fields.next() == Some("dec");
+                continue;
+            }
+            let Some(micros) = parse_time(time) else {
+                continue; // skip header and comment lines
+            };
+
+            match fields.next() {
+                Some("CANFD") => {
+                    rprintln!("WARNING - CAN FD frame skipped: {}", line);
+                    continue;
+                }
+                Some(channel) if channel.bytes().all(|b| b.is_ascii_digit()) => {}
+                _ => continue, // skip events without channel, such as the start of measurement
+            }
+            let (Some(id), Some("Rx" | "Tx")) = (fields.next(), fields.next()) else {
+                continue; // skip events other than frames
+            };
+
+            self.timestamp = micros;
+            match parse_asc_frame(id, &mut fields, if self.decimal { 10 } else { 16 }) {
+                Some(frame) => return Some(frame),
+                None => self.lines.reject(), // invalid frame
+            }
+        }
+        None
+    }
+}
+
+/// Parses an ASC time (`12.345678`) into microseconds.
+///
+/// Returns [`None`] if `time` isn't a valid time.
+fn parse_time(time: &str) -> Option<u64> {
+    if time.split_once('.')?.1.is_empty() {
+        return None;
+    }
+    parse_micros(time, 1_000_000)
+}
+
+/// Parses the identifier `id` and the remaining `fields` of an ASC frame line, numbers being in
+/// the given `radix`.
+///
+/// Returns [`None`] if the frame is invalid.
+fn parse_asc_frame<'a>(
+    id: &str,
+    fields: &mut impl Iterator<Item = &'a str>,
+    radix: u32,
+) -> Option<Frame> {
+    let id = match id.strip_suffix(['x', 'X']) {
+        Some(id) => Id::Extended(ExtendedId::new(u32::from_str_radix(id, radix).ok()?)?),
+        None => Id::Standard(StandardId::new(u16::from_str_radix(id, radix).ok()?)?),
+    };
+
+    match fields.next()? {
+        "d" | "D" => {
+            let dlc = u8::from_str_radix(fields.next()?, radix).ok()?;
+            let data = fields
+                .take(dlc.min(8) as usize) // DLCs above 8 stand for 8 bytes on classic CAN
+                .map(|byte| u8::from_str_radix(byte, radix).ok())
+                .collect::<Option<Vec<u8, 8>>>()?;
+            if data.len() != dlc.min(8) as usize {
+                return None;
+            }
+            Some(Frame::new_data(
+                id,
+                Data::new(&data).expect("data contains at most 8 bytes."),
+            ))
+        }
+        "r" | "R" => {
+            let dlc = fields
+                .next()
+                .and_then(|dlc| u8::from_str_radix(dlc, radix).ok())
+                .unwrap_or(0);
+            Some(Frame::new_remote(id, dlc.min(8)))
+        }
+        _ => None,
+    }
+}
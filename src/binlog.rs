@@ -0,0 +1,230 @@
+//! CANary binary log files abstractions, a compact capture format for high bus loads.
+//!
+//! Files start with a header of [`BIN_HEADER_LEN`] bytes, followed by fixed-size records of
+//! [`BIN_RECORD_LEN`] bytes, one per frame. All numbers are little-endian.
+//!
+//! Header :
+//! ```text
+//! 0..4    magic, "CNB1"
+//! 4       format version, 1
+//! 5       flags, bit 0 set for silent captures
+//! 6..8    length of a record in bytes, 24
+//! 8..12   bitrate in bit/s
+//! 12..16  reserved
+//! 16..32  device name and firmware version, ASCII padded with zeros
+//! ```
+//!
+//! Record :
+//! ```text
+//! 0..8    time of capture in microseconds since boot
+//! 8..12   identifier, without flags
+//! 12      flags, bit 0 set for extended identifiers, bit 1 for remote frames, bit 2 for
+//!         transmitted frames
+//! 13      DLC
+//! 14..16  reserved
+//! 16..24  payload, padded with zeros
+//! ```
+//!
+//! The `tools/cnb2log.py` script converts these files to the `can-utils` log format.
+
+use bxcan::{Data, ExtendedId, Frame, Id, StandardId};
+use rtt_target::rprintln;
+
+use crate::{
+    app::TICK_RATE,
+    sd::{BadLines, LogSource},
+};
+
+/// Length in bytes of the header of binary log files, see [`bin_header()`].
+pub const BIN_HEADER_LEN: usize = 32;
+/// Length in bytes of the records of binary log files, see [`frame_to_bin()`].
+pub const BIN_RECORD_LEN: usize = 24;
+/// Magic number starting binary log files.
+const BIN_MAGIC: &[u8; 4] = b"CNB1";
+/// Version of the binary log format.
+const BIN_VERSION: u8 = 1;
+
+/// Flag of silent captures in the header.
+const SILENT_FLAG: u8 = 0x01;
+/// Flag of extended identifiers in records.
+const EXTENDED_FLAG: u8 = 0x01;
+/// Flag of remote frames in records.
+const REMOTE_FLAG: u8 = 0x02;
+/// Flag of transmitted frames in records.
+const TRANSMITTED_FLAG: u8 = 0x04;
+
+/// Returns the header of a binary log file, for a capture at `bitrate` (in bit/s) in `silent` mode.
+pub fn bin_header(bitrate: u32, silent: bool) -> [u8; BIN_HEADER_LEN] {
+    let mut header = [0; BIN_HEADER_LEN];
+    header[0..4].copy_from_slice(BIN_MAGIC);
+    header[4] = BIN_VERSION;
+    header[5] = if silent { SILENT_FLAG } else { 0 };
+    header[6..8].copy_from_slice(&(BIN_RECORD_LEN as u16).to_le_bytes());
+    header[8..12].copy_from_slice(&bitrate.to_le_bytes());
+
+    let device = concat!("CANary ", env!("CARGO_PKG_VERSION")).as_bytes();
+    let device_len = device.len().min(16);
+    header[16..16 + device_len].copy_from_slice(&device[..device_len]);
+
+    header
+}
+
+/// Formats a given [`Frame`] as a binary log record, given its time of capture in `ticks`.
+pub fn frame_to_bin(frame: &Frame, ticks: u32, transmitted: bool) -> [u8; BIN_RECORD_LEN] {
+    let mut record = [0; BIN_RECORD_LEN];
+    record[0..8].copy_from_slice(&(ticks as u64 * 1_000_000 / TICK_RATE as u64).to_le_bytes());
+
+    let (id, mut flags) = match frame.id() {
+        Id::Standard(id) => (id.as_raw() as u32, 0),
+        Id::Extended(id) => (id.as_raw(), EXTENDED_FLAG),
+    };
+    if frame.is_remote_frame() {
+        flags |= REMOTE_FLAG;
+    }
+    if transmitted {
+        flags |= TRANSMITTED_FLAG;
+    }
+    record[8..12].copy_from_slice(&id.to_le_bytes());
+    record[12] = flags;
+    record[13] = frame.dlc();
+    if let Some(data) = frame.data() {
+        record[16..16 + data.len()].copy_from_slice(data);
+    }
+
+    record
+}
+
+/// Parses a binary log record into a [`Frame`] and its time of capture in microseconds.
+///
+/// Returns [`None`] if the record is invalid.
+fn parse_record(record: &[u8; BIN_RECORD_LEN]) -> Option<(Frame, u64)> {
+    let micros = u64::from_le_bytes(record[0..8].try_into().expect("Slice is 8 bytes long."));
+    let raw_id = u32::from_le_bytes(record[8..12].try_into().expect("Slice is 4 bytes long."));
+    let (flags, dlc) = (record[12], record[13]);
+    if dlc > 8 {
+        return None;
+    }
+
+    let id = if flags & EXTENDED_FLAG != 0 {
+        Id::Extended(ExtendedId::new(raw_id)?)
+    } else {
+        Id::Standard(StandardId::new(u16::try_from(raw_id).ok()?)?)
+    };
+    let frame = if flags & REMOTE_FLAG != 0 {
+        Frame::new_remote(id, dlc)
+    } else {
+        Frame::new_data(
+            id,
+            Data::new(&record[16..16 + dlc as usize]).expect("DLC is at most 8."),
+        )
+    };
+
+    Some((frame, micros))
+}
+
+/// An iterator over CAN frames contained in a CANary binary log file.
+///
+/// The header is checked when reading from the start of the file. Records are read one by one,
+/// invalid ones being skipped and recorded in [`bad_records()`][BinLogsIterator::bad_records()].
+pub struct BinLogsIterator<S: LogSource> {
+    /// The owned [`LogSource`] that will be read for CAN frames.
+    log_file: S,
+    /// Offset in bytes of the next record in the log file, `0` before reading the header.
+    offset: u32,
+    /// Time of the last frame read, in microseconds since boot.
+    timestamp: u64,
+    /// Records skipped since the position of the last seek, numbered from 1.
+    bad_records: BadLines,
+}
+
+impl<S: LogSource> BinLogsIterator<S> {
+    /// Creates a new [`BinLogsIterator`] from a given [`LogSource`].
+    pub fn new(log_file: S) -> Self {
+        Self {
+            log_file,
+            offset: 0,
+            timestamp: 0,
+            bad_records: BadLines::default(),
+        }
+    }
+
+    /// Moves to the record starting `offset` bytes from the start of the log file, as returned by
+    /// [`offset()`][BinLogsIterator::offset()].
+    pub fn seek(&mut self, offset: u32) -> Result<(), ()> {
+        self.log_file.seek(offset)?;
+        self.offset = offset;
+        self.bad_records = BadLines::default();
+        Ok(())
+    }
+
+    /// Returns the offset in bytes of the next record to be read in the log file.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the time of the last frame read, in seconds.
+    pub fn timestamp(&self) -> u32 {
+        (self.timestamp / 1_000_000) as u32
+    }
+
+    /// Returns the time of the last frame read, in microseconds.
+    pub fn timestamp_micros(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns the records skipped so far because they were invalid.
+    pub fn bad_records(&self) -> &BadLines {
+        &self.bad_records
+    }
+
+    /// Returns a mutable reference to the [`LogSource`] being read.
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.log_file
+    }
+
+    /// Fills `buffer` with the next bytes of the log file.
+    ///
+    /// Returns [`None`] if the end of the file is reached first or if a read error occurred.
+    fn read_exact(&mut self, buffer: &mut [u8]) -> Option<()> {
+        let mut read_count = 0;
+        while read_count < buffer.len() {
+            if self.log_file.is_eof() {
+                return None;
+            }
+            read_count += self.log_file.read(&mut buffer[read_count..]).ok()?;
+        }
+        self.offset += read_count as u32;
+        Some(())
+    }
+}
+
+impl<S: LogSource> Iterator for BinLogsIterator<S> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset == 0 {
+            let mut header = [0; BIN_HEADER_LEN];
+            self.read_exact(&mut header)?;
+            if &header[0..4] != BIN_MAGIC
+                || header[4] != BIN_VERSION
+                || header[6..8] != (BIN_RECORD_LEN as u16).to_le_bytes()
+            {
+                rprintln!("WARNING - Not a CANary binary log file");
+                return None;
+            }
+        }
+
+        loop {
+            let mut record = [0; BIN_RECORD_LEN];
+            self.read_exact(&mut record)?;
+            if let Some((frame, micros)) = parse_record(&record) {
+                self.timestamp = micros;
+                return Some(frame);
+            }
+
+            let number = (self.offset - BIN_HEADER_LEN as u32) / BIN_RECORD_LEN as u32;
+            self.bad_records.count += 1;
+            let _ = self.bad_records.first.push(number); // Only the first ones are kept
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Binary-search replay abstractions.
+
+use core::ops::Range;
+
+/// Progress of a binary-search replay, isolating the frames of a log file triggering an effect.
+///
+/// Frames are identified by their index in the log file. The suspected frames are split in two
+/// halves, and the first half is replayed. If the effect is seen, the first half becomes the
+/// suspected frames, otherwise the second half is replayed. If the effect isn't seen with either
+/// half, it needs frames from both and the search ends. The search also ends once a single frame
+/// is suspected.
+#[derive(Clone, Debug)]
+pub struct Bisect {
+    /// Indexes of the suspected frames, [`None`] until the frames of the log file are counted.
+    suspects: Option<Range<u32>>,
+    /// Whether the second half of the suspected frames is tested, the first half having been
+    /// replayed without effect.
+    second_half: bool,
+    /// Whether the tested frames were replayed, waiting for the user to tell if the effect was
+    /// seen.
+    awaiting_answer: bool,
+    /// Whether the search ended.
+    done: bool,
+    /// Whether the result was saved to a new log file.
+    saved: bool,
+}
+
+impl Bisect {
+    /// Creates a new [`Bisect`], whose frames are yet to be counted.
+    pub fn new() -> Self {
+        Self {
+            suspects: None,
+            second_half: false,
+            awaiting_answer: false,
+            done: false,
+            saved: false,
+        }
+    }
+
+    /// Sets the number of frames of the log file, all of them being suspected.
+    pub fn set_frames_count(&mut self, count: u32) {
+        self.suspects = Some(0..count);
+        self.done = count <= 1;
+    }
+
+    /// Returns the indexes of the frames to replay, or [`None`] if the frames weren't counted yet.
+    pub fn tested(&self) -> Option<Range<u32>> {
+        let suspects = self.suspects.clone()?;
+        if self.done {
+            return Some(suspects);
+        }
+
+        let middle = suspects.start + (suspects.end - suspects.start) / 2;
+        Some(if self.second_half {
+            middle..suspects.end
+        } else {
+            suspects.start..middle
+        })
+    }
+
+    /// Marks the tested frames as replayed, waiting for an answer.
+    pub fn replayed(&mut self) {
+        self.awaiting_answer = !self.done;
+    }
+
+    /// Returns whether the tested frames were replayed and an answer is expected.
+    pub fn is_awaiting_answer(&self) -> bool {
+        self.awaiting_answer
+    }
+
+    /// Narrows the suspected frames given whether the effect was seen during the last replay.
+    ///
+    /// Does nothing if no answer is expected.
+    pub fn answer(&mut self, effect_seen: bool) {
+        if !self.awaiting_answer {
+            return;
+        }
+        self.awaiting_answer = false;
+
+        if effect_seen {
+            self.suspects = self.tested();
+            self.second_half = false;
+            self.done = self.suspects.as_ref().is_none_or(|s| s.len() <= 1);
+        } else if self.second_half {
+            self.done = true;
+        } else {
+            self.second_half = true;
+        }
+    }
+
+    /// Returns the indexes of the isolated frames once the search ended.
+    pub fn result(&self) -> Option<Range<u32>> {
+        if self.done {
+            self.suspects.clone()
+        } else {
+            None
+        }
+    }
+
+    /// Marks the result as saved.
+    pub fn saved(&mut self) {
+        self.saved = true;
+    }
+
+    /// Returns whether the result was saved.
+    pub fn is_saved(&self) -> bool {
+        self.saved
+    }
+}
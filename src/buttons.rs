@@ -133,6 +133,7 @@ impl ButtonPanelState {
     }
 
     /// Debugging function printing to RTT the [`ButtonPanelState`].
+    #[allow(semicolon_in_expressions_from_non_local_macros)]
     pub fn print(&self) {
         rprint!(
             "{} {} {} {} {}",
@@ -1,6 +1,6 @@
 //! CAN bus wrappers and relevant abstractions.
 
-use bxcan::{filter::Mask32, Fifo, Frame};
+use bxcan::{filter::Mask32, ExtendedId, Fifo, Frame, Id, StandardId};
 use heapless::spsc::Producer;
 use stm32f1xx_hal::{
     afio,
@@ -15,6 +15,8 @@ use crate::app::PCLK1_CLOCK_RATE_MHZ;
 pub struct CanContext {
     /// The wrapped [`bxcan::Can<Can<CAN1>>`] instance
     pub bus: bxcan::Can<Can<CAN1>>,
+    /// Whether the CAN bus was enabled in TX or RX mode since last disabled
+    enabled: bool,
 }
 
 impl CanContext {
@@ -37,7 +39,10 @@ impl CanContext {
             .modify_filters()
             .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
 
-        Self { bus: can_bus }
+        Self {
+            bus: can_bus,
+            enabled: false,
+        }
     }
 
     /// Enables the CAN bus in TX mode given a [`Bitrate`] and [`EmissionMode`].
@@ -59,6 +64,7 @@ impl CanContext {
             .enable();
         self.bus
             .enable_interrupt(bxcan::Interrupt::TransmitMailboxEmpty);
+        self.enabled = true;
     }
 
     /// Enables the CAN bus in RX mode given a [`Bitrate`] and `silent` flag.
@@ -73,6 +79,14 @@ impl CanContext {
             .enable();
         self.bus
             .enable_interrupt(bxcan::Interrupt::Fifo0MessagePending);
+        self.enabled = true;
+    }
+
+    /// Enables the [`Fifo0MessagePending`](bxcan::Interrupt::Fifo0MessagePending) interrupt,
+    /// allowing frames to be received while in TX mode.
+    pub fn enable_rx_interrupt(&mut self) {
+        self.bus
+            .enable_interrupt(bxcan::Interrupt::Fifo0MessagePending);
     }
 
     /// Disables the CAN bus.
@@ -86,9 +100,51 @@ impl CanContext {
         self.bus.abort(bxcan::Mailbox::Mailbox0);
         self.bus.abort(bxcan::Mailbox::Mailbox1);
         self.bus.abort(bxcan::Mailbox::Mailbox2);
+        self.enabled = false;
+    }
+
+    /// Clears the request completed flag of a transmit mailbox, returning the mailbox and whether
+    /// its frame was successfully transmitted.
+    ///
+    /// This is [`bxcan::Can::clear_request_completed_flag()`], which clears the transmission
+    /// status of the mailbox without returning it. A request completes without transmission when
+    /// it is aborted, or when arbitration is lost or an error occurs without automatic
+    /// retransmission.
+    pub fn clear_request_completed_flag(&mut self) -> Option<(bxcan::Mailbox, bool)> {
+        // SAFETY: Only the request completed flags are written, like `bus` does.
+        let can = unsafe { &*CAN1::ptr() };
+        let tsr = can.tsr.read();
+        if tsr.rqcp0().bit_is_set() {
+            can.tsr.write(|w| w.rqcp0().set_bit());
+            Some((bxcan::Mailbox::Mailbox0, tsr.txok0().bit_is_set()))
+        } else if tsr.rqcp1().bit_is_set() {
+            can.tsr.write(|w| w.rqcp1().set_bit());
+            Some((bxcan::Mailbox::Mailbox1, tsr.txok1().bit_is_set()))
+        } else if tsr.rqcp2().bit_is_set() {
+            can.tsr.write(|w| w.rqcp2().set_bit());
+            Some((bxcan::Mailbox::Mailbox2, tsr.txok2().bit_is_set()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the CAN bus is enabled, in TX or RX mode.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
     }
 }
 
+/// A CAN frame seen on the bus, queued in the CAN RX Queue for logging.
+#[derive(Clone, Debug)]
+pub struct BusFrame {
+    /// The frame itself.
+    pub frame: Frame,
+    /// Time of reception, or of transmission completion, in ticks since boot.
+    pub ticks: u32,
+    /// Whether the frame was transmitted by the device rather than received.
+    pub transmitted: bool,
+}
+
 /// Enqueues `frame` in the provided `queue` and pends the
 /// [`USB_HP_CAN_TX`][pac::Interrupt::USB_HP_CAN_TX] interrupt, allowing the frame to be sent
 /// immediately.
@@ -101,6 +157,20 @@ pub fn enqueue_frame<const N: usize>(
     Ok(())
 }
 
+/// Parses a hexadecimal CAN identifier.
+///
+/// Identifiers of up to 3 digits are standard ones, longer identifiers are extended ones (as in log
+/// files, where extended identifiers are written with 8 digits). Returns [`None`] if `s` isn't a
+/// valid identifier.
+pub fn parse_id(s: &str) -> Option<Id> {
+    let raw = u32::from_str_radix(s, 16).ok()?;
+    if s.len() <= 3 {
+        StandardId::new(raw as u16).map(Id::Standard)
+    } else {
+        ExtendedId::new(raw).map(Id::Extended)
+    }
+}
+
 /// A CAN bus bit rate
 ///
 /// Available [`Bitrate`]s are the common bitrates defined by CANopen.
@@ -137,6 +207,26 @@ impl Bitrate {
         Self::Br125kbps
     }
 
+    /// Returns the [`Bitrate`] of `kbps` kilobits per second, rounded down (`83` for 83.333 kbps).
+    ///
+    /// Returns [`None`] if there is no such [`Bitrate`].
+    pub fn from_kbps(kbps: u32) -> Option<Self> {
+        [
+            Self::Br1000kbps,
+            Self::Br800kbps,
+            Self::Br500kbps,
+            Self::Br250kbps,
+            Self::Br125kbps,
+            Self::Br100kbps,
+            Self::Br83kbps,
+            Self::Br50kbps,
+            Self::Br20kbps,
+            Self::Br10kbps,
+        ]
+        .into_iter()
+        .find(|bitrate| *bitrate as u32 / 1000 == kbps)
+    }
+
     /// Returns the bit timing corresponding to the [`Bitrate`].
     ///
     /// Bit timings are used to set the bit rate when enabling a [`CanContext`].
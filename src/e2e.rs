@@ -0,0 +1,242 @@
+//! Counters and checksums abstractions, protecting emitted frames end-to-end.
+
+use core::str::FromStr;
+
+use bxcan::{Frame, Id};
+use heapless::Vec;
+
+use crate::{app::MAX_PROTECTIONS, can::parse_id};
+
+/// A counter or checksum recomputed in every emitted frame of an identifier.
+#[derive(Debug)]
+struct Protection {
+    /// Identifier of the protected frames.
+    id: Id,
+    /// Counter or checksum written in the frames.
+    kind: ProtectionKind,
+    /// Value of the counter written in the next frame.
+    counter: u8,
+}
+
+/// Counter or checksum written by a [`Protection`].
+#[derive(Clone, Copy, Debug)]
+enum ProtectionKind {
+    /// Counter written in the bits of the mask of a byte, wrapping to `0` after a maximum value.
+    Counter { byte: usize, mask: u8, max: u8 },
+    /// XOR of every other byte of the payload, written in a byte.
+    Xor { byte: usize },
+    /// SAE J1850 CRC8 of every other byte of the payload, written in a byte.
+    Crc8 { byte: usize },
+    /// AUTOSAR E2E profile 1 (`DATAID_BOTH` mode): CRC written in a byte and counter (`0` to
+    /// `14`) in the low nibble of another byte.
+    Profile1 {
+        crc_byte: usize,
+        counter_byte: usize,
+        data_id: u16,
+    },
+    /// AUTOSAR E2E profile 2: CRC written in byte `0` and counter (`0` to `15`) in the low nibble
+    /// of byte `1`.
+    ///
+    /// # To-Do
+    /// - [ ] Handle a list of 16 data IDs, the same data ID being currently used for every
+    ///   counter value.
+    Profile2 { data_id: u8 },
+}
+
+/// Counters and checksums recomputed in emitted frames, read from the [`E2E_FILE_NAME`] file.
+///
+/// The file contains one protection per line, lines starting with `#` are comments. Identifiers,
+/// masks and data IDs are hexadecimal, byte indexes and counter maximums are decimal :
+/// ```text
+/// counter 123 1 F0        # counter in the bits F0 of byte 1, incremented for every frame
+/// counter 123 1 F0 14     # same counter, wrapping to 0 after 14
+/// xor 123 7               # XOR of the other bytes in byte 7
+/// crc8 123 7              # SAE J1850 CRC8 of the other bytes in byte 7
+/// e2e1 456 0 1 0042       # AUTOSAR E2E profile 1, CRC in byte 0, counter in byte 1, data ID 0042
+/// e2e2 789 2A             # AUTOSAR E2E profile 2 with data ID 2A
+/// ```
+///
+/// Protections are applied in the order of the file, so counters should be defined before the
+/// checksums covering them.
+///
+/// [`E2E_FILE_NAME`]: crate::app::E2E_FILE_NAME
+#[derive(Debug)]
+pub struct Protections {
+    /// Protections applied to emitted frames.
+    protections: Vec<Protection, MAX_PROTECTIONS>,
+}
+
+impl Protections {
+    /// Creates a new [`Protections`] instance, leaving frames unchanged.
+    pub fn new() -> Self {
+        Self {
+            protections: Vec::new(),
+        }
+    }
+
+    /// Parses a line of the [`E2E_FILE_NAME`][crate::app::E2E_FILE_NAME] file into `self`.
+    ///
+    /// Empty and comment lines are ignored. Returns a short error message if the line is invalid.
+    pub fn parse_line(&mut self, line: &str) -> Result<(), &'static str> {
+        let mut fields = line.split('#').next().unwrap_or("").split_whitespace();
+        let Some(kind) = fields.next() else {
+            return Ok(());
+        };
+        let id = fields.next().and_then(parse_id).ok_or("Bad ID")?;
+
+        let kind = match kind {
+            "counter" => {
+                let byte = parse_byte_index(fields.next())?;
+                let mask = fields
+                    .next()
+                    .and_then(|m| u8::from_str_radix(m, 16).ok())
+                    .filter(|m| *m != 0)
+                    .ok_or("Bad mask")?;
+                let mask_max = mask >> mask.trailing_zeros();
+                let max = match fields.next() {
+                    Some(max) => u8::from_str(max)
+                        .ok()
+                        .filter(|m| *m <= mask_max)
+                        .ok_or("Bad max")?,
+                    None => mask_max,
+                };
+                ProtectionKind::Counter { byte, mask, max }
+            }
+            "xor" => ProtectionKind::Xor {
+                byte: parse_byte_index(fields.next())?,
+            },
+            "crc8" => ProtectionKind::Crc8 {
+                byte: parse_byte_index(fields.next())?,
+            },
+            "e2e1" => ProtectionKind::Profile1 {
+                crc_byte: parse_byte_index(fields.next())?,
+                counter_byte: parse_byte_index(fields.next())?,
+                data_id: fields
+                    .next()
+                    .and_then(|d| u16::from_str_radix(d, 16).ok())
+                    .ok_or("Bad data ID")?,
+            },
+            "e2e2" => ProtectionKind::Profile2 {
+                data_id: fields
+                    .next()
+                    .and_then(|d| u8::from_str_radix(d, 16).ok())
+                    .ok_or("Bad data ID")?,
+            },
+            _ => return Err("Bad kind"),
+        };
+
+        if fields.next().is_some() {
+            return Err("Extra field");
+        }
+
+        self.protections
+            .push(Protection {
+                id,
+                kind,
+                counter: 0,
+            })
+            .map_err(|_| "Too many")
+    }
+
+    /// Recomputes the counters and checksums of `frame`, returning the updated frame.
+    ///
+    /// Remote frames are left unchanged, as are frames too short to contain a protection.
+    pub fn apply(&mut self, frame: Frame) -> Frame {
+        let Some(data) = frame.data() else {
+            return frame;
+        };
+
+        let mut payload = *data;
+        for protection in self.protections.iter_mut() {
+            if protection.id == frame.id() {
+                protection.apply(&mut payload);
+            }
+        }
+
+        Frame::new_data(frame.id(), payload)
+    }
+}
+
+impl Protection {
+    /// Writes the counter or checksum in `payload` and increments the counter.
+    fn apply(&mut self, payload: &mut [u8]) {
+        let len = payload.len();
+        match self.kind {
+            ProtectionKind::Counter { byte, mask, max } if byte < len => {
+                let value = self.counter << mask.trailing_zeros();
+                payload[byte] = (payload[byte] & !mask) | (value & mask);
+                self.counter = if self.counter >= max {
+                    0
+                } else {
+                    self.counter + 1
+                };
+            }
+            ProtectionKind::Xor { byte } if byte < len => {
+                payload[byte] = payload
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != byte)
+                    .fold(0, |xor, (_, b)| xor ^ b);
+            }
+            ProtectionKind::Crc8 { byte } if byte < len => {
+                let crc = payload
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != byte)
+                    .fold(0xFF, |crc, (_, b)| crc8_update(crc, *b, 0x1D));
+                payload[byte] = crc ^ 0xFF;
+            }
+            ProtectionKind::Profile1 {
+                crc_byte,
+                counter_byte,
+                data_id,
+            } if crc_byte < len && counter_byte < len => {
+                payload[counter_byte] = (payload[counter_byte] & 0xF0) | self.counter;
+                self.counter = (self.counter + 1) % 15;
+
+                let crc = data_id
+                    .to_le_bytes()
+                    .iter()
+                    .chain(
+                        payload
+                            .iter()
+                            .enumerate()
+                            .filter(|(i, _)| *i != crc_byte)
+                            .map(|(_, b)| b),
+                    )
+                    .fold(0x00, |crc, b| crc8_update(crc, *b, 0x1D));
+                payload[crc_byte] = crc;
+            }
+            ProtectionKind::Profile2 { data_id } if len >= 2 => {
+                payload[1] = (payload[1] & 0xF0) | self.counter;
+                self.counter = (self.counter + 1) % 16;
+
+                let crc = payload[1..]
+                    .iter()
+                    .chain(&[data_id])
+                    .fold(0xFF, |crc, b| crc8_update(crc, *b, 0x2F));
+                payload[0] = crc ^ 0xFF;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Parses a byte index field, between `0` and `7`.
+fn parse_byte_index(field: Option<&str>) -> Result<usize, &'static str> {
+    field
+        .and_then(|i| usize::from_str(i).ok())
+        .filter(|i| *i < 8)
+        .ok_or("Bad index")
+}
+
+/// Updates a CRC8 register with a `byte`, given the CRC `polynomial` (MSB first).
+fn crc8_update(crc: u8, byte: u8, polynomial: u8) -> u8 {
+    (0..8).fold(crc ^ byte, |crc, _| {
+        if crc & 0x80 != 0 {
+            (crc << 1) ^ polynomial
+        } else {
+            crc << 1
+        }
+    })
+}
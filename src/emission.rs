@@ -0,0 +1,561 @@
+//! Emissions run by the [`sd_reader()`][crate::app::sd_reader()] task, one function per kind of
+//! emitted file or replay mode.
+
+use core::cmp::Ordering;
+
+use bxcan::Frame;
+use embedded_sdmmc::{self as sdmmc, ShortFileName};
+use heapless::{
+    spsc::{Consumer, Producer},
+    String, Vec,
+};
+use rtic::Mutex;
+use rtic_monotonics::{
+    fugit::{ExtU32, Instant},
+    Monotonic,
+};
+use rtt_target::rprintln;
+
+use crate::{
+    app::{
+        Mono, CAN_TX_QUEUE_CAPACITY, LOG_INDEX_STRIDE, MAX_REWRITE_RULES, SD_RX_QUEUE_CAPACITY,
+        TICK_RATE,
+    },
+    bisect::Bisect,
+    can::{enqueue_frame, Bitrate, BusFrame, CanContext, EmissionMode},
+    e2e::Protections,
+    filter::IdFilter,
+    range::{LogRange, RangeUnit},
+    render::formatted_string,
+    rules::{apply_rules, Rule},
+    sd::*,
+    state::{wait_while_paused, StateManager},
+};
+
+/// Error of an emission, a short message and the number of the faulty line (`0` if not related
+/// to a line), stored in [`State::error`][crate::state::State::error].
+type EmissionError = (&'static str, u32);
+
+/// Returns the name of a new log file, unique to the current time.
+fn new_log_name() -> String<12> {
+    formatted_string(format_args!("{:08}.log", Mono::now().ticks()))
+        .expect("Formatted args should fit.")
+}
+
+/// Rewrite rules, identifier filter and range applied to a replayed log file.
+pub struct ReplayContext {
+    /// Rewrite rules of the log file, see [`load_rules()`].
+    rules: Vec<Rule, MAX_REWRITE_RULES>,
+    /// Identifier filter of the log file, see [`load_filter()`].
+    filter: IdFilter,
+    /// Part of the log file replayed.
+    range: LogRange,
+    /// Position the replay of the [`range`][Self::range] starts at, see [`locate_log_range()`].
+    start: LogPosition,
+}
+
+impl ReplayContext {
+    /// Creates an empty context replaying `range`, to be filled by [`load()`][Self::load].
+    pub fn new(range: LogRange) -> Self {
+        Self {
+            rules: Vec::new(),
+            filter: IdFilter::new(),
+            range,
+            start: LogPosition {
+                offset: 0,
+                index: 0,
+                origin: 0,
+            },
+        }
+    }
+
+    /// Loads the context of the log file `file_name` of `dir`, replayed with `id_filter` (see
+    /// [`load_filter()`]).
+    ///
+    /// The context is filled in place as it is too large to be moved around on the stack.
+    ///
+    /// Returns a short error message and the number of the faulty line if the rules or filter
+    /// are invalid, or if the range can't be located.
+    pub fn load<V: Mutex<T = VolumeManager>>(
+        &mut self,
+        volume_manager: &mut V,
+        dir: sdmmc::RawDirectory,
+        file_name: &ShortFileName,
+        id_filter: &IdFilter,
+    ) -> Result<(), EmissionError> {
+        with_dir(volume_manager, dir, |dir| {
+            load_rules(dir, file_name, &mut self.rules)?;
+            rprintln!("Loaded {} rewrite rules", self.rules.len());
+            load_filter(dir, file_name, id_filter, &mut self.filter)?;
+            self.start = locate_log_range(dir, file_name, &self.range)?;
+            Ok(())
+        })
+    }
+
+    /// Returns `frame` as replayed, or [`None`] if it is filtered out or dropped by a rule.
+    pub fn apply(&self, frame: Frame) -> Option<Frame> {
+        if !self.filter.keeps(&frame) {
+            return None;
+        }
+        apply_rules(&self.rules, frame)
+    }
+}
+
+/// Resources and settings of an emission, shared by the emission modes.
+///
+/// The [`VolumeManager`] is given to each emission mode separately, log files being read through
+/// it while frames are sent (see [`SharedLogFiles`]).
+pub struct Emitter<'a, C, R, S>
+where
+    C: Mutex<T = CanContext>,
+    R: Mutex<T = Consumer<'static, BusFrame, SD_RX_QUEUE_CAPACITY>>,
+    S: Mutex<T = StateManager>,
+{
+    /// CAN bus, to receive frames or change the bitrate during the emission.
+    pub can: C,
+    /// Consumer end of the CAN RX queue, for emissions receiving frames.
+    pub rx_queue: R,
+    /// System state manager, checked for pauses and stops.
+    pub state_manager: S,
+    /// Producer end of the CAN TX queue.
+    pub tx_queue: &'a mut Producer<'static, Frame, CAN_TX_QUEUE_CAPACITY>,
+    /// Directory of the emitted file, kept open through a raw handle (see [`with_dir()`]).
+    pub dir: sdmmc::RawDirectory,
+    /// Counters and checksums updated in every frame sent, see [`load_protections()`].
+    pub protections: Protections,
+    /// Remaining number of runs of the emission, or [`None`] to run until stopped.
+    pub runs: Option<u8>,
+    /// Bitrate the CAN bus was enabled at.
+    pub bitrate: Bitrate,
+    /// Mode the CAN bus was enabled in.
+    pub emission_mode: EmissionMode,
+    /// Identifier filter of the emission settings, see [`load_filter()`].
+    pub id_filter: IdFilter,
+    /// Part of the log files replayed, see [`locate_log_range()`].
+    pub range: LogRange,
+}
+
+impl<C, R, S> Emitter<'_, C, R, S>
+where
+    C: Mutex<T = CanContext>,
+    R: Mutex<T = Consumer<'static, BusFrame, SD_RX_QUEUE_CAPACITY>>,
+    S: Mutex<T = StateManager>,
+{
+    /// Returns whether the emission is still running, waiting here while it is paused (see
+    /// [`wait_while_paused()`]).
+    pub async fn is_running(&mut self) -> bool {
+        wait_while_paused(&mut self.state_manager).await.0
+    }
+
+    /// Waits until a frame can be queued in the CAN TX queue, returning whether the emission is
+    /// still running.
+    async fn wait_ready(&mut self) -> bool {
+        while !self.tx_queue.ready() {
+            if !self.is_running().await {
+                return false;
+            }
+            Mono::delay(1.millis()).await;
+        }
+        self.is_running().await
+    }
+
+    /// Queues `frame` with its [`protections`][Self::protections] updated, returning whether
+    /// the emission is still running. Nothing is queued if it was stopped meanwhile.
+    async fn send(&mut self, frame: Frame) -> bool {
+        if !self.wait_ready().await {
+            return false;
+        }
+        enqueue_frame(self.tx_queue, self.protections.apply(frame)).expect("tx_queue is ready.");
+        true
+    }
+
+    /// Waits until every queued frame was sent, returning whether the emission is still running.
+    pub async fn flush(&mut self) -> bool {
+        while self.tx_queue.len() != 0 {
+            if !self.is_running().await {
+                return false;
+            }
+            Mono::delay(1.millis()).await;
+        }
+        self.is_running().await
+    }
+
+    /// Waits until `end`, returning whether the emission is still running.
+    async fn wait_until(&mut self, end: Instant<u32, 1, TICK_RATE>) -> bool {
+        while Mono::now() < end {
+            if !self.is_running().await {
+                return false;
+            }
+            Mono::delay(1.millis()).await;
+        }
+        self.is_running().await
+    }
+
+    /// Enables the reception of frames during the emission, dropping the frames received before.
+    fn listen(&mut self) {
+        self.can.lock(|can| can.enable_rx_interrupt());
+        self.rx_queue
+            .lock(|rx_queue| while rx_queue.dequeue().is_some() {});
+    }
+
+    /// Returns the next frame of the CAN RX queue, if any.
+    fn receive(&mut self) -> Option<BusFrame> {
+        self.rx_queue.lock(|rx_queue| rx_queue.dequeue())
+    }
+
+    /// Writes the frames of the CAN RX queue to the transcript of `files`.
+    fn record<V: Mutex<T = VolumeManager>>(&mut self, files: &mut SharedLogFiles<'_, V>) {
+        while let Some(bus_frame) = self.receive() {
+            let log_line = frame_to_log(&bus_frame.frame, bus_frame.ticks, bus_frame.transmitted);
+            let _ = files.write(log_line.as_bytes());
+        }
+    }
+
+    /// Opens the log file `file_name`, creating the log file `output_name` alongside if any (see
+    /// [`SharedLogFiles`]).
+    fn open_logs<'v, V: Mutex<T = VolumeManager>>(
+        &self,
+        volume_manager: &'v mut V,
+        file_name: &ShortFileName,
+        output_name: Option<&str>,
+    ) -> Result<LogsIterator<SharedLogFiles<'v, V>>, EmissionError> {
+        let files = SharedLogFiles::open(volume_manager, self.dir, file_name, output_name)
+            .map_err(|_| ("Can't open", 0))?;
+        Ok(LogsIterator::new(files, file_name))
+    }
+
+    /// Reads the whole log file `file_name` without sending anything, returning the number of
+    /// frames read and the lines that couldn't be parsed, or [`None`] if stopped before its end.
+    ///
+    /// Other files are reported as errors.
+    pub async fn validate<V: Mutex<T = VolumeManager>>(
+        &mut self,
+        volume_manager: &mut V,
+        file_name: &ShortFileName,
+    ) -> Result<Option<(u32, BadLines)>, EmissionError> {
+        if matches!(file_name.extension(), b"SCH" | b"LST") {
+            return Err(("Not a log", 0));
+        }
+
+        let mut logs = self.open_logs(volume_manager, file_name, None)?;
+        let mut frames_count = 0;
+        while self.is_running().await && logs.next().is_some() {
+            frames_count += 1;
+        }
+
+        Ok(self
+            .is_running()
+            .await
+            .then(|| (frames_count, logs.bad_lines().clone())))
+    }
+
+    /// Sends the frames of the schedule `file_name` periodically until stopped, see
+    /// [`Scheduler`][crate::schedule::Scheduler].
+    ///
+    /// The schedule is restarted on resume rather than sending the frames missed while paused.
+    pub async fn run_schedule<V: Mutex<T = VolumeManager>>(
+        &mut self,
+        volume_manager: &mut V,
+        file_name: &ShortFileName,
+    ) -> Result<(), EmissionError> {
+        let mut scheduler = with_dir(volume_manager, self.dir, |dir| {
+            load_schedule(dir, file_name)
+        })?;
+
+        scheduler.start(Mono::now());
+        loop {
+            match wait_while_paused(&mut self.state_manager).await {
+                (false, _) => break,
+                (true, true) => scheduler.start(Mono::now()), // Frames due while paused are not sent
+                (true, false) => {}
+            }
+            match scheduler.next_frame(Mono::now()) {
+                Some(frame) => {
+                    if !self.send(frame).await {
+                        break;
+                    }
+                }
+                None => {
+                    // Wake up at least every 10 ms to notice the end of the emission
+                    let deadline = scheduler.next_deadline();
+                    let max_deadline = Mono::now() + 10.millis();
+                    Mono::delay_until(if deadline < max_deadline {
+                        deadline
+                    } else {
+                        max_deadline
+                    })
+                    .await;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replays the frames of `logs` in the range of `context` once, returning whether the
+    /// emission can go on.
+    ///
+    /// If `transcript` is set, the frames of the CAN RX queue are written to the output file of
+    /// `logs` as frames are queued.
+    async fn replay_once<V: Mutex<T = VolumeManager>>(
+        &mut self,
+        logs: &mut LogsIterator<SharedLogFiles<'_, V>>,
+        context: &ReplayContext,
+        transcript: bool,
+    ) -> bool {
+        if logs.seek(context.start.offset).is_err() {
+            return false;
+        }
+
+        let mut index = context.start.index;
+        while let Some(frame) = logs.next() {
+            let time = logs.timestamp().saturating_sub(context.start.origin);
+            let position = context.range.position(index, time);
+            index += 1;
+            match position {
+                Ordering::Less => continue,
+                Ordering::Greater => break,
+                Ordering::Equal => {}
+            }
+            let Some(frame) = context.apply(frame) else {
+                continue;
+            };
+
+            if transcript {
+                while !self.tx_queue.ready() && self.is_running().await {
+                    self.record(logs.source_mut());
+                    Mono::delay(1.millis()).await;
+                }
+            }
+            if !self.send(frame).await {
+                return false;
+            }
+            if transcript {
+                self.record(logs.source_mut());
+            }
+        }
+
+        true
+    }
+
+    /// Replays the log file `file_name` [`runs`][Self::runs] times, restricted to
+    /// [`range`][Self::range].
+    ///
+    /// If `transcript` is set, reception is enabled and the frames sent and received are written
+    /// to a new log file of the directory.
+    pub async fn replay<V: Mutex<T = VolumeManager>>(
+        &mut self,
+        volume_manager: &mut V,
+        file_name: &ShortFileName,
+        transcript: bool,
+    ) -> Result<(), EmissionError> {
+        let mut context = ReplayContext::new(self.range);
+        context.load(volume_manager, self.dir, file_name, &self.id_filter)?;
+
+        let transcript_name = transcript.then(new_log_name);
+        if let Some(transcript_name) = &transcript_name {
+            rprintln!("Transcript written to '{}'", transcript_name);
+        }
+        let mut logs = self.open_logs(volume_manager, file_name, transcript_name.as_deref())?;
+        if transcript {
+            let _ = logs
+                .source_mut()
+                .write(b"# Frames sent (T) and received by CANary\n");
+            self.listen();
+        }
+
+        while self.runs.unwrap_or(u8::MAX) > 0 && self.is_running().await {
+            if !self.replay_once(&mut logs, &context, transcript).await {
+                break;
+            }
+            if let Some(ref mut n) = self.runs {
+                *n -= 1;
+            }
+        }
+
+        if transcript {
+            // Record the last frames sent and the responses to them
+            while self.tx_queue.len() != 0 && self.is_running().await {
+                self.record(logs.source_mut());
+                Mono::delay(1.millis()).await;
+            }
+            self.record(logs.source_mut());
+        }
+
+        Ok(())
+    }
+
+    /// Emits the log files of the playlist `file_name` in sequence [`runs`][Self::runs] times,
+    /// see [`Playlist`][crate::playlist::Playlist].
+    ///
+    /// The bitrate is changed between log files if requested, once the previous frames were sent.
+    pub async fn play_list<V: Mutex<T = VolumeManager>>(
+        &mut self,
+        volume_manager: &mut V,
+        file_name: &ShortFileName,
+    ) -> Result<(), EmissionError> {
+        let playlist = with_dir(volume_manager, self.dir, |dir| {
+            load_playlist(dir, file_name)
+        })?;
+
+        let mut context = ReplayContext::new(LogRange::new());
+        let mut current_bitrate = self.bitrate;
+        'runs: while self.runs.unwrap_or(u8::MAX) > 0 && self.is_running().await {
+            for entry in playlist.entries() {
+                context.load(volume_manager, self.dir, &entry.file_name, &self.id_filter)?;
+
+                // The bitrate is only changed once the last frame left the mailboxes
+                let entry_bitrate = entry.bitrate.unwrap_or(self.bitrate);
+                if entry_bitrate as u32 != current_bitrate as u32 {
+                    while self.tx_queue.len() != 0
+                        || !self.can.lock(|can| can.bus.is_transmitter_idle())
+                    {
+                        if !self.is_running().await {
+                            break 'runs;
+                        }
+                        Mono::delay(1.millis()).await;
+                    }
+                    self.can
+                        .lock(|can| can.enable_tx(entry_bitrate, self.emission_mode));
+                    current_bitrate = entry_bitrate;
+                }
+
+                // The file may have been removed since the playlist was loaded
+                let mut logs = self
+                    .open_logs(volume_manager, &entry.file_name, None)
+                    .map_err(|_| ("Missing file", entry.line_number))?;
+                for _ in 0..entry.count {
+                    if !self.replay_once(&mut logs, &context, false).await {
+                        break 'runs;
+                    }
+                }
+                drop(logs);
+
+                if !self.wait_until(Mono::now() + entry.delay_ms.millis()).await {
+                    break 'runs;
+                }
+            }
+
+            if let Some(ref mut n) = self.runs {
+                *n -= 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replays once the frames of the log file `file_name` tested by `bisect`, or writes the
+    /// isolated frames to a new log file of the directory once the search ended.
+    ///
+    /// Frames are indexed among the ones kept by the filter and the rewrite rules, as when sent.
+    pub async fn bisect<V: Mutex<T = VolumeManager>>(
+        &mut self,
+        volume_manager: &mut V,
+        file_name: &ShortFileName,
+        bisect: &mut Bisect,
+    ) -> Result<(), EmissionError> {
+        let mut context = ReplayContext::new(LogRange::new());
+        context.load(volume_manager, self.dir, file_name, &self.id_filter)?;
+
+        if bisect.tested().is_none() {
+            let logs = self.open_logs(volume_manager, file_name, None)?;
+            bisect.set_frames_count(logs.filter_map(|frame| context.apply(frame)).count() as u32);
+        }
+
+        if let Some(result) = bisect.result() {
+            if result.is_empty() {
+                return Err(("No frame", 0));
+            }
+
+            let result_name = new_log_name();
+            rprintln!("Bisect result written to '{}'", result_name);
+            let mut logs = self.open_logs(volume_manager, file_name, Some(&result_name))?;
+            let _ = logs.source_mut().write(
+                formatted_string::<64>(format_args!(
+                    "# Frames {} to {} isolated by CANary bisect\n",
+                    result.start,
+                    result.end - 1
+                ))
+                .expect("Formatted args should fit.")
+                .as_bytes(),
+            );
+
+            let mut index = 0;
+            while let Some(frame) = logs.next() {
+                // Frames are indexed among the ones sent, but saved as read
+                if context.apply(frame.clone()).is_none() {
+                    continue;
+                }
+                if result.contains(&index) {
+                    let log_line = frame_to_log_at(&frame, logs.timestamp_micros(), false);
+                    let _ = logs.source_mut().write(log_line.as_bytes());
+                }
+                index += 1;
+            }
+            bisect.saved();
+        } else {
+            let tested = bisect.tested().expect("Frames were counted above.");
+            let logs = self.open_logs(volume_manager, file_name, None)?;
+            for frame in logs
+                .filter_map(|frame| context.apply(frame))
+                .skip(tested.start as usize)
+                .take(tested.len())
+            {
+                if !self.send(frame).await {
+                    break;
+                }
+            }
+
+            if self.flush().await {
+                bisect.replayed(); // Replayed again on next start if stopped early
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends the frame of the log file `file_name` at `step_index`, skipping the frames filtered
+    /// out or dropped by the rewrite rules.
+    ///
+    /// Past the first [`LOG_INDEX_STRIDE`] frames, the log file is read from the closest position
+    /// of its index (see [`locate_log_range()`]) rather than from its start.
+    ///
+    /// Returns the index of the frame and the frame sent, or [`None`] if stopped before.
+    pub async fn step<V: Mutex<T = VolumeManager>>(
+        &mut self,
+        volume_manager: &mut V,
+        file_name: &ShortFileName,
+        step_index: u32,
+    ) -> Result<Option<(u32, Frame)>, EmissionError> {
+        let range = if step_index < LOG_INDEX_STRIDE {
+            LogRange::new()
+        } else {
+            LogRange {
+                unit: RangeUnit::Index,
+                from: step_index,
+                to: 0,
+            }
+        };
+        let mut context = ReplayContext::new(range);
+        context.load(volume_manager, self.dir, file_name, &self.id_filter)?;
+
+        let mut logs = self.open_logs(volume_manager, file_name, None)?;
+        logs.seek(context.start.offset)
+            .map_err(|_| ("Can't read", 0))?;
+        let Some((index, frame)) = logs
+            .enumerate()
+            .map(|(index, frame)| (context.start.index + index as u32, frame))
+            .skip_while(|(index, _)| *index < step_index)
+            .find_map(|(index, frame)| Some((index, context.apply(frame)?)))
+        else {
+            return Err(("End of log", 0));
+        };
+
+        if !self.wait_ready().await {
+            return Ok(None);
+        }
+        let frame = self.protections.apply(frame);
+        enqueue_frame(self.tx_queue, frame.clone()).expect("tx_queue is ready.");
+        Ok(Some((index, frame)))
+    }
+}
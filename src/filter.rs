@@ -0,0 +1,188 @@
+//! Identifier filters abstractions, replaying only some frames of a log file.
+
+use bxcan::{Frame, Id};
+use heapless::Vec;
+
+use crate::app::MAX_FILTER_RANGES;
+
+/// Largest identifier value, the one of the last extended identifier.
+pub const MAX_ID: u32 = 0x1FFF_FFFF;
+
+/// Way an [`IdFilter`] treats the frames of its identifier ranges.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FilterMode {
+    /// Every frame is replayed.
+    Off,
+    /// Only the frames in the ranges are replayed.
+    Keep,
+    /// The frames in the ranges are not replayed.
+    Drop,
+    /// The filter is read from the `.FLT` file of the log file, see [`IdFilter::parse_line()`].
+    File,
+}
+
+impl FilterMode {
+    /// Increments `self` to next [`FilterMode`], wrapping around.
+    pub fn increment(&mut self) {
+        *self = match self {
+            Self::Off => Self::Keep,
+            Self::Keep => Self::Drop,
+            Self::Drop => Self::File,
+            Self::File => Self::Off,
+        }
+    }
+
+    /// Decrements `self` to previous [`FilterMode`], wrapping around.
+    pub fn decrement(&mut self) {
+        *self = match self {
+            Self::Keep => Self::Off,
+            Self::Drop => Self::Keep,
+            Self::File => Self::Drop,
+            Self::Off => Self::File,
+        }
+    }
+}
+
+/// A filter keeping or dropping frames of a log file by identifier, applied before the rewrite
+/// rules.
+///
+/// Identifiers are compared by value, a standard identifier and an extended identifier with the
+/// same value being treated alike.
+///
+/// Filters are either edited on the settings screen or read from a `.FLT` file. The file contains
+/// one identifier or inclusive range of identifiers per line (hexadecimal), lines starting with
+/// `#` are comments. Frames in the ranges are kept unless a `drop` line is found :
+/// ```text
+/// drop            # don't replay the frames below, keep the others
+/// 3B0             # identifier 3B0
+/// 100-1FF         # identifiers 100 to 1FF
+/// ```
+#[derive(Clone, Debug)]
+pub struct IdFilter {
+    /// Way the frames of the ranges are treated.
+    pub mode: FilterMode,
+    /// Inclusive ranges of identifiers.
+    ranges: Vec<(u32, u32), MAX_FILTER_RANGES>,
+}
+
+impl IdFilter {
+    /// Creates a new [`IdFilter`] keeping every frame.
+    pub fn new() -> Self {
+        Self {
+            mode: FilterMode::Off,
+            ranges: Vec::new(),
+        }
+    }
+
+    /// Returns whether `frame` should be replayed.
+    pub fn keeps(&self, frame: &Frame) -> bool {
+        let id = match frame.id() {
+            Id::Standard(id) => id.as_raw() as u32,
+            Id::Extended(id) => id.as_raw(),
+        };
+        let in_ranges = self
+            .ranges
+            .iter()
+            .any(|(first, last)| (*first..=*last).contains(&id));
+
+        match self.mode {
+            FilterMode::Keep => in_ranges,
+            FilterMode::Drop => !in_ranges,
+            FilterMode::Off | FilterMode::File => true,
+        }
+    }
+
+    /// Returns the ranges of identifiers, in the order they were added.
+    pub fn ranges(&self) -> &[(u32, u32)] {
+        &self.ranges
+    }
+
+    /// Parses a line of a `.FLT` file into `self`.
+    ///
+    /// Empty and comment lines are ignored. Returns a short error message if the line is invalid.
+    pub fn parse_line(&mut self, line: &str) -> Result<(), &'static str> {
+        let mut fields = line.split('#').next().unwrap_or("").split_whitespace();
+        let Some(field) = fields.next() else {
+            return Ok(());
+        };
+        if fields.next().is_some() {
+            return Err("Extra field");
+        }
+
+        let parse = |id: &str| {
+            u32::from_str_radix(id, 16)
+                .ok()
+                .filter(|id| *id <= MAX_ID)
+                .ok_or("Bad ID")
+        };
+        let range = match field {
+            "keep" | "drop" => {
+                self.mode = if field == "keep" {
+                    FilterMode::Keep
+                } else {
+                    FilterMode::Drop
+                };
+                return Ok(());
+            }
+            _ => match field.split_once('-') {
+                Some((first, last)) => (parse(first)?, parse(last)?),
+                None => (parse(field)?, parse(field)?),
+            },
+        };
+        if range.0 > range.1 {
+            return Err("Bad range");
+        }
+
+        self.ranges.push(range).map_err(|_| "Too many")
+    }
+
+    /// Steps a bound of the range at `index` by `step`, up or down.
+    ///
+    /// Stepping a bound of the range following the last one (`index` equal to the number of
+    /// ranges) adds a new range, unless there are already [`MAX_FILTER_RANGES`] ranges. The first
+    /// bound pushes the last one up, and the range is removed when its last bound goes below its
+    /// first one.
+    pub fn step_range(&mut self, index: usize, is_last: bool, step: u32, up: bool) {
+        if index == self.ranges.len() && self.ranges.push((0, 0)).is_err() {
+            return;
+        }
+        let Some((first, last)) = self.ranges.get_mut(index) else {
+            return;
+        };
+
+        match (is_last, up) {
+            (false, true) => {
+                *first = first.saturating_add(step).min(MAX_ID);
+                *last = (*last).max(*first);
+            }
+            (false, false) => *first = first.saturating_sub(step),
+            (true, true) => *last = last.saturating_add(step).min(MAX_ID),
+            (true, false) => match last.checked_sub(step) {
+                Some(value) if value >= *first => *last = value,
+                _ => {
+                    self.ranges.remove(index);
+                }
+            },
+        }
+    }
+}
+
+/// Steps the digit edited on a settings screen, `step` being a power of `base`.
+///
+/// Steps go from `1` to the largest power of `base` not above `max` (`0x1000_0000` for the
+/// hexadecimal identifiers of the filter settings screen), wrapping around.
+pub fn step_digit(step: u32, up: bool, base: u32, max: u32) -> u32 {
+    let next = |step: u32| step.checked_mul(base).filter(|next| *next <= max);
+    match next(step) {
+        Some(next) if up => next,
+        None if up => 1,
+        _ if step > 1 => step / base,
+        _ => {
+            let mut last = 1;
+            while let Some(next) = next(last) {
+                last = next;
+            }
+            last
+        }
+    }
+}
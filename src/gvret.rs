@@ -0,0 +1,149 @@
+//! GVRET CSV log files abstractions, as saved and loaded by SavvyCAN.
+
+use bxcan::{Data, ExtendedId, Frame, Id, StandardId};
+use heapless::Vec;
+
+use crate::sd::{BadLines, LogLines, LogSource};
+
+/// An iterator over CAN frames contained in a GVRET CSV log file.
+///
+/// Lines look as follows, the time being in microseconds :
+/// ```text
+/// Time Stamp,ID,Extended,Bus,LEN,D1,D2,D3,D4,D5,D6,D7,D8
+/// 375767000,00000001,false,0,8,01,23,45,67,89,AB,CD,EF,
+/// 375768000,12345678,true,0,2,AA,BB,
+/// ```
+///
+/// The header line is skipped. Files saved by recent SavvyCAN versions have an additional `Dir`
+/// column after `Extended`, which is detected from the header and ignored. Frames with a length
+/// but no payload bytes are read as remote frames, as SavvyCAN can't tell them apart otherwise.
+pub struct GvretLogsIterator<S: LogSource> {
+    /// The lines of the log file that will be scanned for CAN frames.
+    lines: LogLines<S>,
+    /// Time of the last frame read, in microseconds.
+    timestamp: u64,
+    /// Whether the lines have a `Dir` column, as read from the header.
+    has_direction: bool,
+}
+
+impl<S: LogSource> GvretLogsIterator<S> {
+    /// Creates a new [`GvretLogsIterator`] from a given [`LogSource`].
+    pub fn new(log_file: S) -> Self {
+        Self {
+            lines: LogLines::new(log_file),
+            timestamp: 0,
+            has_direction: false,
+        }
+    }
+
+    /// Moves to the line starting `offset` bytes from the start of the log file, as returned by
+    /// [`offset()`][GvretLogsIterator::offset()].
+    pub fn seek(&mut self, offset: u32) -> Result<(), ()> {
+        self.lines.seek(offset)
+    }
+
+    /// Returns the offset in bytes of the next line to be read in the log file.
+    pub fn offset(&self) -> u32 {
+        self.lines.offset()
+    }
+
+    /// Returns the time of the last frame read, in seconds.
+    pub fn timestamp(&self) -> u32 {
+        (self.timestamp / 1_000_000) as u32
+    }
+
+    /// Returns the time of the last frame read, in microseconds.
+    pub fn timestamp_micros(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns the lines skipped so far because they couldn't be parsed.
+    pub fn bad_lines(&self) -> &BadLines {
+        self.lines.bad_lines()
+    }
+
+    /// Returns a mutable reference to the [`LogSource`] being read.
+    pub fn source_mut(&mut self) -> &mut S {
+        self.lines.source_mut()
+    }
+}
+
+impl<S: LogSource> Iterator for GvretLogsIterator<S> {
+    type Item = Frame;
+
+    /// Reads the next [`Frame`] from the log file.
+    ///
+    /// If any error is encountered while reading the file, [`None`] is returned and iteration ends.
+    /// Lines that can't be parsed are skipped, and recorded in [`bad_lines()`][Self::bad_lines()].
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(line) = self.lines.next_line() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with("Time Stamp") {
+                self.has_direction = line.split(',').any(|column| column.trim() == "Dir");
+                continue;
+            }
+
+            match parse_gvret_line(line, self.has_direction) {
+                Some((frame, micros)) => {
+                    self.timestamp = micros;
+                    return Some(frame);
+                }
+                None => self.lines.reject(),
+            }
+        }
+        None
+    }
+}
+
+/// Parses a GVRET CSV line into a [`Frame`] and its time of capture in microseconds, the line
+/// having a `Dir` column if `has_direction` is set.
+///
+/// Returns [`None`] if the line is invalid.
+fn parse_gvret_line(line: &str, has_direction: bool) -> Option<(Frame, u64)> {
+    let mut fields = line.split(',').map(str::trim);
+    let micros = fields.next()?.parse::<u64>().ok()?;
+    let id = fields.next()?;
+    let extended = fields.next()?;
+    if has_direction {
+        fields.next()?;
+    }
+    fields.next()?; // bus, ignored
+
+    Some((parse_gvret_frame(id, extended, &mut fields)?, micros))
+}
+
+/// Parses the hexadecimal identifier `id`, the `extended` flag and the remaining `fields` (length
+/// and payload bytes) of a GVRET CSV line.
+///
+/// Returns [`None`] if the frame is invalid.
+fn parse_gvret_frame<'a>(
+    id: &str,
+    extended: &str,
+    fields: &mut impl Iterator<Item = &'a str>,
+) -> Option<Frame> {
+    let id = match extended {
+        "true" | "True" | "1" => Id::Extended(ExtendedId::new(u32::from_str_radix(id, 16).ok()?)?),
+        "false" | "False" | "0" => {
+            Id::Standard(StandardId::new(u16::from_str_radix(id, 16).ok()?)?)
+        }
+        _ => return None,
+    };
+    let len = fields.next()?.parse::<u8>().ok().filter(|len| *len <= 8)?;
+
+    let data = fields
+        .filter(|byte| !byte.is_empty())
+        .take(len as usize)
+        .map(|byte| u8::from_str_radix(byte, 16).ok())
+        .collect::<Option<Vec<u8, 8>>>()?;
+    match data.len() {
+        0 if len > 0 => Some(Frame::new_remote(id, len)),
+        n if n == len as usize => Some(Frame::new_data(
+            id,
+            Data::new(&data).expect("data contains at most 8 bytes."),
+        )),
+        _ => None,
+    }
+}
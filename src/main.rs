@@ -5,22 +5,38 @@
 use panic_rtt_target as _;
 use rtic::app;
 
+mod asc;
+mod binlog;
+mod bisect;
 mod buttons;
 mod can;
+mod e2e;
+mod emission;
+mod filter;
+mod gvret;
+mod monitor;
+mod playlist;
+mod range;
 mod render;
+mod rules;
+mod schedule;
 mod sd;
+mod signal;
 mod spi;
 mod state;
+mod trc;
 
 #[app(device = stm32f1xx_hal::pac, peripherals = true, dispatchers = [TIM2, TIM3, TIM4])]
 mod app {
     use bxcan::Frame;
+    use core::mem::MaybeUninit;
     use embedded_sdmmc as sdmmc;
     use fugit::Instant;
     use heapless::{
         spsc::{Consumer, Producer, Queue},
-        String, Vec,
+        String,
     };
+    use rtic::Exclusive;
     use rtic_monotonics::systick::prelude::*;
     use rtt_target::{rprintln, rtt_init_print};
     use ssd1306::{prelude::*, I2CDisplayInterface, Ssd1306};
@@ -35,8 +51,12 @@ mod app {
     };
 
     use crate::{
+        binlog::{bin_header, frame_to_bin},
         buttons::*,
         can::*,
+        e2e::Protections,
+        emission::Emitter,
+        monitor::FrameMonitor,
         render::{
             draw_header, flush_text_line, formatted_string, TEXT_LINE_2, TEXT_LINE_3, TEXT_LINE_4,
             TEXT_LINE_5,
@@ -80,6 +100,56 @@ mod app {
     /// are much slower than a full speed CAN bus.
     pub const SD_RX_QUEUE_CAPACITY: usize = 64;
 
+    /// Number of frames kept by the [`FrameMonitor`](crate::monitor::FrameMonitor).
+    ///
+    /// This is also the number of lines displayed on the live frames page.
+    pub const LIVE_FRAMES_AMOUNT: usize = 8;
+    /// Refresh delay of the live pages.
+    ///
+    /// Drawing the live pages blocks the CAN tasks, refreshing too often will make frames be lost.
+    pub const LIVE_REFRESH_DELAY_MS: u32 = 250;
+    /// Maximum number of identifiers tracked by the sniffer page.
+    ///
+    /// Identifiers seen once the table is full will not be displayed.
+    pub const MAX_SNIFFED_IDS: usize = 32;
+    /// Delay during which a changed byte stays highlighted on the sniffer page.
+    pub const SNIFFER_HIGHLIGHT_MS: u32 = 1_000;
+    /// Delay between two checks of the end of a pause of the emission.
+    pub const PAUSE_POLL_DELAY_MS: u32 = 10;
+    /// Number of values kept by the plot page.
+    ///
+    /// One value is drawn per pixel column, this should match the display width.
+    pub const PLOT_SAMPLES_AMOUNT: usize = 128;
+    /// Maximum number of signals loaded from the DBC file.
+    ///
+    /// Signals defined after the first [`MAX_DBC_SIGNALS`] ones will not be offered on the plot page.
+    pub const MAX_DBC_SIGNALS: usize = 16;
+    /// Maximum number of rewrite rules loaded for an emission.
+    ///
+    /// Rule files defining more rules are rejected, nothing being sent.
+    pub const MAX_REWRITE_RULES: usize = 16;
+    /// Maximum number of identifier ranges of an emission filter.
+    pub const MAX_FILTER_RANGES: usize = 16;
+    /// Maximum number of frames sent periodically by a schedule.
+    pub const MAX_SCHEDULES: usize = 16;
+    /// Maximum number of log files listed in a playlist.
+    pub const MAX_PLAYLIST_ENTRIES: usize = 16;
+    /// Maximum number of counters and checksums recomputed in emitted frames.
+    pub const MAX_PROTECTIONS: usize = 16;
+    /// Number of frames between two entries of a log file index.
+    ///
+    /// Replaying a range of a log file parses at most [`LOG_INDEX_STRIDE`] frames before its start.
+    pub const LOG_INDEX_STRIDE: u32 = 1024;
+    /// Number of bad lines whose numbers are kept while reading a log file.
+    ///
+    /// Bad lines past these ones are only counted, see [`BadLines`][crate::sd::BadLines].
+    pub const MAX_BAD_LINES: usize = 4;
+    /// Name of the DBC file, looked for at the root of the SD card during initialisation.
+    pub const DBC_FILE_NAME: &str = "CANARY.DBC";
+    /// Name of the counters and checksums file, looked for at the root of the SD card before each
+    /// emission.
+    pub const E2E_FILE_NAME: &str = "CANARY.E2E";
+
     /// Debouncing delay applied to button inputs.
     ///
     /// Button presses for a same button closer that [`DEBOUNCE_DELAY_MS`] will be ignored.
@@ -114,6 +184,15 @@ mod app {
         volume_manager: VolumeManager,
         /// System state manager, wraps a [`Display`](crate::render::Display) and [`State`](State).
         state_manager: StateManager,
+        /// Monitor of the CAN traffic, filled by [`can_sender()`] and [`can_receiver()`] while an
+        /// emission or capture is running.
+        monitor: &'static mut FrameMonitor,
+        /// Producer end of the CAN RX queue. Used by [`can_receiver()`], and by [`can_sender()`]
+        /// to record transmitted frames in transcripts.
+        can_rx_producer: Producer<'static, BusFrame, SD_RX_QUEUE_CAPACITY>,
+        /// Consumer end of the CAN RX queue. Used by [`sd_writer()`] during captures and by
+        /// [`sd_reader()`] during transcripts.
+        can_rx_consumer: Consumer<'static, BusFrame, SD_RX_QUEUE_CAPACITY>,
     }
 
     #[local]
@@ -122,10 +201,6 @@ mod app {
         can_tx_producer: Producer<'static, Frame, CAN_TX_QUEUE_CAPACITY>,
         /// Consumer end of the CAN TX queue. Used by [`can_sender()`].
         can_tx_consumer: Consumer<'static, Frame, CAN_TX_QUEUE_CAPACITY>,
-        /// Producer end of the CAN RX queue. Used by [`can_receiver()`].
-        can_rx_producer: Producer<'static, Frame, SD_RX_QUEUE_CAPACITY>,
-        /// Consumer end of the CAN RX queue. Used by [`sd_writer()`].
-        can_rx_consumer: Consumer<'static, Frame, SD_RX_QUEUE_CAPACITY>,
         /// Status LED control pin. Used by [`blinker()`].
         status_led: Pin<'C', 15, Output>,
     }
@@ -146,7 +221,8 @@ mod app {
     #[init(
         local = [
             q_tx: Queue<Frame, CAN_TX_QUEUE_CAPACITY> = Queue::new(),
-            q_rx: Queue<Frame, SD_RX_QUEUE_CAPACITY> = Queue::new(),
+            q_rx: Queue<BusFrame, SD_RX_QUEUE_CAPACITY> = Queue::new(),
+            monitor: MaybeUninit<FrameMonitor> = MaybeUninit::uninit(),
         ]
     )]
     fn init(mut cx: init::Context) -> (Shared, Local) {
@@ -235,7 +311,7 @@ mod app {
         // Init SD Card
         rprintln!("-> SD Card");
         flush_text_line(&mut display, "-> SD card", TEXT_LINE_5);
-        let volume_manager = {
+        let mut volume_manager = {
             let sd_spi = SpiWrapper {
                 spi: Spi::spi2(
                     cx.device.SPI2,
@@ -264,9 +340,15 @@ mod app {
             sdmmc::VolumeManager::<_, _, 2, 2, 1>::new_with_limits(sd_card, FakeTimeSource {}, 5000)
         };
 
-        // Init StateManager
+        // Load DBC signals
+        let dbc_signals = load_dbc_signals(&mut volume_manager);
+        rprintln!("Loaded {} DBC signals", dbc_signals.len());
+
+        // Init FrameMonitor and StateManager
+        let mut monitor = cx.local.monitor.write(FrameMonitor::new());
+        monitor.set_dbc_signals(dbc_signals);
         let mut state_manager = StateManager::default_with_display(display);
-        state_manager.render();
+        state_manager.render(&mut Exclusive(&mut monitor));
         state_updater::spawn().expect("State updater wasn't started yet.");
 
         rprintln!("Initialisation done");
@@ -277,12 +359,13 @@ mod app {
                 button_panel,
                 volume_manager,
                 state_manager,
+                monitor,
+                can_rx_producer,
+                can_rx_consumer,
             },
             Local {
                 can_tx_producer,
                 can_tx_consumer,
-                can_rx_producer,
-                can_rx_consumer,
                 status_led,
             },
         )
@@ -321,33 +404,61 @@ mod app {
     /// The [`USB_HP_CAN_TX()`] interrupt must be triggered the first time items are added to the Queue
     /// to initiate transmission. Every successful transmission will trigger the interrupt again and
     /// thus consume the CAN TX Queue until empty.
+    ///
+    /// If [`FrameMonitor::transcript`] is set, frames successfully transmitted are
+    /// queued in the CAN RX Queue with their completion time. Frames that couldn't be sent without
+    /// automatic retransmission are not recorded.
     #[task(
         binds = USB_HP_CAN_TX,
         priority = 4,
-        shared = [can, state_manager],
-        local = [can_tx_consumer]
+        shared = [can, monitor, can_rx_producer],
+        local = [can_tx_consumer, in_flight: [Option<Frame>; 3] = [None, None, None]]
     )]
     fn can_sender(mut cx: can_sender::Context) {
         let mut can = cx.shared.can;
         let tx_queue = cx.local.can_tx_consumer;
+        let in_flight = cx.local.in_flight;
 
         can.lock(|can| {
-            can.bus.clear_tx_interrupt();
+            let transcript = cx.shared.monitor.lock(|monitor| monitor.transcript);
+            while let Some((mailbox, transmitted)) = can.clear_request_completed_flag() {
+                let Some(frame) = in_flight[mailbox as usize].take() else {
+                    continue;
+                };
+                if transcript && transmitted {
+                    let bus_frame = BusFrame {
+                        frame,
+                        ticks: Mono::now().ticks(),
+                        transmitted: true,
+                    };
+                    if cx
+                        .shared
+                        .can_rx_producer
+                        .lock(|rx_queue| rx_queue.enqueue(bus_frame))
+                        .is_err()
+                    {
+                        rprintln!("WARNING - Couldn't queue a sent frame for writing");
+                    }
+                }
+            }
 
             if can.bus.is_transmitter_idle() {
                 while let Some(frame) = tx_queue.peek() {
                     rprintln!("Transmitting {:?}", frame);
                     match can.bus.transmit(frame) {
                         Ok(status) => {
-                            tx_queue.dequeue();
                             assert_eq!(
                                 status.dequeued_frame(),
                                 None,
                                 "All mailboxes should have been empty"
                             );
-                            cx.shared
-                                .state_manager
-                                .lock(|sm| sm.state.success_count += 1);
+                            cx.shared.monitor.lock(|monitor| {
+                                monitor.frames_count += 1;
+                                if !monitor.frozen {
+                                    monitor.record(frame, Mono::now().ticks());
+                                }
+                            });
+                            in_flight[status.mailbox() as usize] = tx_queue.dequeue();
                         }
                         Err(nb::Error::WouldBlock) => break,
                         Err(_) => unreachable!(),
@@ -363,20 +474,46 @@ mod app {
     /// must be enabled for this function to trigger.
     ///
     /// If the SD RX Queue is full, the received frame will be dumped and a warning will be emitted.
+    /// If [`FrameMonitor::logging`] is not set, frames are not queued and only counted.
+    ///
+    /// Frames received during a capture are also recorded in the [`FrameMonitor`] for the live
+    /// pages. Frames received while [`FrameMonitor::transcript`] is set (the RX interrupt is only
+    /// enabled for transcripts during emissions) are queued for [`sd_reader()`] and not counted.
     #[task(
         binds = USB_LP_CAN_RX0,
         priority = 5,
-        shared = [can],
-        local = [can_rx_producer],
+        shared = [can, monitor, can_rx_producer],
     )]
     fn can_receiver(mut cx: can_receiver::Context) {
-        let rx_queue = cx.local.can_rx_producer;
         cx.shared.can.lock(|can| {
             if let Ok(frame) = can.bus.receive() {
                 rprintln!("Received {:?}", frame);
-                if rx_queue.ready() {
-                    rx_queue.enqueue(frame).expect("rx_queue is ready.");
-                } else {
+                let queuing = cx.shared.monitor.lock(|monitor| {
+                    if monitor.transcript {
+                        return true;
+                    }
+                    if !monitor.frozen {
+                        monitor.record(&frame, Mono::now().ticks());
+                    }
+                    if !monitor.logging {
+                        monitor.frames_count += 1;
+                    }
+                    monitor.logging
+                });
+                if !queuing {
+                    return;
+                }
+                let bus_frame = BusFrame {
+                    frame,
+                    ticks: Mono::now().ticks(),
+                    transmitted: false,
+                };
+                if cx
+                    .shared
+                    .can_rx_producer
+                    .lock(|rx_queue| rx_queue.enqueue(bus_frame))
+                    .is_err()
+                {
                     rprintln!("WARNING - Couldn't queue a frame for writing");
                 }
             }
@@ -392,7 +529,7 @@ mod app {
     #[task(
         binds = EXTI4,
         priority = 8,
-        shared = [button_panel, state_manager],
+        shared = [button_panel, state_manager, monitor],
         local = [last_press_time: Option<Instant<u32, 1, TICK_RATE>> = None],
     )]
     fn clicked_ok(mut cx: clicked_ok::Context) {
@@ -405,7 +542,9 @@ mod app {
         };
 
         rprintln!("Pressed OK");
-        cx.shared.state_manager.lock(|sm| sm.press(Button::Ok));
+        cx.shared
+            .state_manager
+            .lock(|sm| sm.press(Button::Ok, &mut cx.shared.monitor));
         let _ = state_updater::spawn();
     }
 
@@ -418,7 +557,7 @@ mod app {
     #[task(
         binds = EXTI0,
         priority = 8,
-        shared = [button_panel, state_manager],
+        shared = [button_panel, state_manager, monitor],
         local = [last_press_time: Option<Instant<u32, 1, TICK_RATE>> = None],
     )]
     fn clicked_up(mut cx: clicked_up::Context) {
@@ -431,7 +570,9 @@ mod app {
         };
 
         rprintln!("Pressed UP");
-        cx.shared.state_manager.lock(|sm| sm.press(Button::Up));
+        cx.shared
+            .state_manager
+            .lock(|sm| sm.press(Button::Up, &mut cx.shared.monitor));
         let _ = state_updater::spawn();
     }
 
@@ -444,7 +585,7 @@ mod app {
     #[task(
         binds = EXTI1,
         priority = 8,
-        shared = [button_panel, state_manager],
+        shared = [button_panel, state_manager, monitor],
         local = [last_press_time: Option<Instant<u32, 1, TICK_RATE>> = None],
     )]
     fn clicked_down(mut cx: clicked_down::Context) {
@@ -457,7 +598,9 @@ mod app {
         };
 
         rprintln!("Pressed DOWN");
-        cx.shared.state_manager.lock(|sm| sm.press(Button::Down));
+        cx.shared
+            .state_manager
+            .lock(|sm| sm.press(Button::Down, &mut cx.shared.monitor));
         let _ = state_updater::spawn();
     }
 
@@ -470,7 +613,7 @@ mod app {
     #[task(
         binds = EXTI2,
         priority = 8,
-        shared = [button_panel, state_manager],
+        shared = [button_panel, state_manager, monitor],
         local = [last_press_time: Option<Instant<u32, 1, TICK_RATE>> = None],
     )]
     fn clicked_right(mut cx: clicked_right::Context) {
@@ -483,7 +626,9 @@ mod app {
         };
 
         rprintln!("Pressed RIGHT");
-        cx.shared.state_manager.lock(|sm| sm.press(Button::Right));
+        cx.shared
+            .state_manager
+            .lock(|sm| sm.press(Button::Right, &mut cx.shared.monitor));
         let _ = state_updater::spawn();
     }
 
@@ -496,7 +641,7 @@ mod app {
     #[task(
         binds = EXTI3,
         priority = 8,
-        shared = [button_panel, state_manager],
+        shared = [button_panel, state_manager, monitor],
         local = [last_press_time: Option<Instant<u32, 1, TICK_RATE>> = None],
     )]
     fn clicked_left(mut cx: clicked_left::Context) {
@@ -509,23 +654,27 @@ mod app {
         };
 
         rprintln!("Pressed LEFT");
-        cx.shared.state_manager.lock(|sm| sm.press(Button::Left));
+        cx.shared
+            .state_manager
+            .lock(|sm| sm.press(Button::Left, &mut cx.shared.monitor));
         let _ = state_updater::spawn();
     }
 
     /// Function propagating updates done to the [`StateManager`].
     ///
     /// It matches the current screen being displayed and the state of the system to determine which
-    /// function to spawn of interrupt to set up. It handles starting and stopping reading and writing
+    /// function to spawn of interrupt to set up. Emissions and captures are only started if the
+    /// [`CanContext`] isn't already enabled. It handles starting and stopping reading and writing
     /// operations and thus has higher priority than all other blocking operations. If necessary, it
     /// will also trigger a [render](StateManager::render()).
     ///
     /// It can be called after user inputs (buttons) or when a reading or writing operation finishes.
     #[task(
         priority = 7,
-        shared = [state_manager, can],
+        shared = [state_manager, can, monitor],
     )]
     async fn state_updater(cx: state_updater::Context) {
+        let mut monitor = cx.shared.monitor;
         (cx.shared.state_manager, cx.shared.can).lock(|sm, can| {
             match (sm.current_screen(), &sm.state) {
                 (Screen::EmissionSelection { .. }, State { running: true, .. }) => {
@@ -538,7 +687,7 @@ mod app {
                     Screen::EmissionSelection { .. } | Screen::CaptureSelection { .. },
                     State { running: false, .. },
                 ) => {
-                    sm.render();
+                    sm.render(&mut monitor);
                 }
                 (
                     Screen::Emission,
@@ -548,10 +697,17 @@ mod app {
                         emission_mode,
                         ..
                     },
-                ) => {
+                ) if !can.is_enabled() => {
                     can.enable_tx(*bitrate, *emission_mode);
+                    let transcript = sm.state.replay_mode == ReplayMode::Record;
+                    monitor.lock(|monitor| {
+                        monitor.clear();
+                        monitor.transcript = transcript;
+                        monitor.logging = false;
+                    });
                     sd_reader::spawn()
                         .expect("sd_reader shouldn't be running (running was false).");
+                    let _ = live_renderer::spawn(); // Can be still waiting for its last refresh
                 }
                 (
                     Screen::Capture,
@@ -559,21 +715,60 @@ mod app {
                         running: true,
                         bitrate,
                         capture_silent,
+                        capture_logging,
                         ..
                     },
-                ) => {
+                ) if !can.is_enabled() => {
                     can.enable_rx(*bitrate, *capture_silent);
-                    let _ = sd_writer::spawn(); // Can be already spawned since [`state_updater()`] will be called again if a button other than OK is pressed.
+                    monitor.lock(|monitor| {
+                        monitor.clear();
+                        monitor.transcript = false;
+                        monitor.logging = *capture_logging;
+                    });
+                    if *capture_logging {
+                        let _ = sd_writer::spawn(); // Can be still flushing the RX queue of the last capture
+                    }
+                    let _ = live_renderer::spawn(); // Can be still waiting for its last refresh
                 }
                 (Screen::Emission | Screen::Capture, State { running: false, .. }) => {
                     can.disable();
-                    sm.render();
+                    sm.render(&mut monitor);
                 }
                 _ => {}
             }
         });
     }
 
+    /// Function refreshing the live pages.
+    ///
+    /// While [`State::running`] is set, it renders the [`StateManager`] every
+    /// [`LIVE_REFRESH_DELAY_MS`] if a live page other than [`LivePage::Summary`] is displayed and
+    /// not frozen.
+    ///
+    /// It shares the priority of [`state_updater()`] so that it can preempt the SD tasks.
+    #[task(
+        priority = 7,
+        shared = [state_manager, monitor],
+    )]
+    async fn live_renderer(mut cx: live_renderer::Context) {
+        loop {
+            Mono::delay(LIVE_REFRESH_DELAY_MS.millis()).await;
+
+            let running = cx.shared.state_manager.lock(|sm| {
+                if sm.state.running
+                    && sm.state.live_page != LivePage::Summary
+                    && !sm.state.live_frozen
+                {
+                    sm.render(&mut cx.shared.monitor);
+                }
+                sm.state.running
+            });
+            if !running {
+                break;
+            }
+        }
+    }
+
     /// Function indexing the Micro SD.
     ///
     /// When called, it will read the path to index from [`State::dir_path`] and populate
@@ -597,7 +792,7 @@ mod app {
                     .expect("Path only contains existing items.");
             }
 
-            sm.state.dir_content = Vec::new();
+            sm.state.dir_content.clear();
             index_dir(&mut dir, &mut sm.state.dir_content, dirs_only).unwrap();
 
             rprintln!("{:?}", sm.state.dir_content);
@@ -611,30 +806,106 @@ mod app {
     /// Function reading CAN frames from a file on the Micro SD.
     ///
     /// When called, it will resolve the path given in [`State::dir_path`] and start reading the
-    /// file's content. See [`sd::CanLogsIterator`] for implementation details. It will loop over the
+    /// file's content. See [`sd::LogsIterator`] for implementation details, log files being read in
+    /// the `can-utils` or Vector ASC format depending on their extension. It will loop over the
     /// file [`State::emission_count`] times except if it is `0`, in which case it will loop until
     /// [`State::running`] is set to `false`. The frames read from the file will be queued to the CAN
     /// TX Queue to be read by [can_sender()].
     ///
+    /// Frames read from a log file are rewritten by the rules of the `.RUL` file of the same name,
+    /// if any (see [`Rule`][crate::rules::Rule]). If a rule is invalid, nothing is sent and the
+    /// error is stored in [`State::error`].
+    ///
+    /// Before being rewritten, frames are filtered by identifier with [`State::id_filter`], or the
+    /// `.FLT` file of the same name if requested (see [`load_filter()`]). If the filter file is
+    /// missing or invalid, nothing is sent and the error is stored in [`State::error`].
+    ///
+    /// If the file is a schedule (`.SCH` extension), its frames are sent periodically by a
+    /// [`Scheduler`][crate::schedule::Scheduler] until stopped, regardless of
+    /// [`State::emission_count`]. Parse errors are stored in [`State::error`].
+    ///
+    /// Every frame is updated with the counters and checksums of the [`E2E_FILE_NAME`] file just
+    /// before being queued (see [`Protections`][crate::e2e::Protections]). If the file is invalid,
+    /// nothing is sent and the error is stored in [`State::error`].
+    ///
+    /// If [`State::replay_mode`] is [`ReplayMode::Record`], log files are replayed with reception
+    /// enabled, and the frames sent and received are written to a new log file of the same
+    /// directory as they are queued in the CAN RX Queue (see [`SharedLogFiles`]).
+    ///
+    /// If [`State::bisect`] is set, only the frames tested by the
+    /// [`Bisect`][crate::bisect::Bisect] are replayed once, regardless of
+    /// [`State::emission_count`]. Once the search ended, the isolated frames are written to a new
+    /// log file of the same directory instead.
+    ///
+    /// If [`State::replay_mode`] is [`ReplayMode::Step`], only the frame at [`State::step_index`]
+    /// is sent, regardless of [`State::emission_count`]. Frames dropped by the rewrite rules are
+    /// skipped. The frame sent is stored in [`State::step_sent`] and the index moved past it.
+    ///
+    /// If [`State::replay_mode`] is [`ReplayMode::Validate`], nothing is sent : the whole log file
+    /// is read, and the lines that couldn't be parsed are stored in [`State::validation`] (the
+    /// number of frames read in [`State::success_count`]). Other files are reported as errors.
+    ///
+    /// If the file is a playlist (`.LST` extension), its log files are emitted in sequence by
+    /// [`State::emission_count`] runs, regardless of [`State::replay_mode`] (see
+    /// [`Playlist`][crate::playlist::Playlist]). The bitrate is changed between log files if
+    /// requested, once the previous frames were sent. Parse errors and missing log files are
+    /// stored in [`State::error`].
+    ///
+    /// Log files replayed in [`ReplayMode::Normal`] or [`ReplayMode::Record`] are restricted to
+    /// [`State::replay_range`]. The replay starts from the closest position found in the index of
+    /// the log file (see [`locate_log_range()`]), frames before the range being skipped.
+    ///
+    /// While [`State::paused`] is set, the emission waits where it is (see [`wait_while_paused()`]),
+    /// keeping the position in the file and the remaining repetitions. Schedules are restarted on
+    /// resume rather than sending the frames missed while paused.
+    ///
+    /// Each kind of file and replay mode is run by a method of [`Emitter`]. The SD card is only
+    /// locked for each access, so that the emission waits for the CAN TX Queue asynchronously.
+    ///
     /// Once reading is done, [`State::running`] will be set to false and [`state_updater()`] will be
     /// called.
     #[task(
         priority = 1,
-        shared = [volume_manager, state_manager],
+        shared = [can, volume_manager, state_manager, can_rx_consumer],
         local = [can_tx_producer],
     )]
-    async fn sd_reader(mut cx: sd_reader::Context) {
-        let tx_queue = cx.local.can_tx_producer;
-        let mut emission_count = match cx.shared.state_manager.lock(|sm| sm.state.emission_count) {
-            0 => None,
-            n => Some(n),
-        };
+    async fn sd_reader(cx: sd_reader::Context) {
+        let sd_reader::SharedResources {
+            can,
+            mut volume_manager,
+            mut state_manager,
+            can_rx_consumer,
+            ..
+        } = cx.shared;
+
+        let (replay_mode, mut bisect, step_index) = state_manager.lock(|sm| {
+            (
+                sm.state.replay_mode,
+                sm.state.bisect.clone(),
+                sm.state.step_index,
+            )
+        });
+        let (runs, bitrate, emission_mode, id_filter, range) = state_manager.lock(|sm| {
+            (
+                match sm.state.emission_count {
+                    0 => None,
+                    n => Some(n),
+                },
+                sm.state.bitrate,
+                sm.state.emission_mode,
+                sm.state.id_filter.clone(),
+                sm.state.replay_range,
+            )
+        });
+        let mut step_sent = None;
+        let mut validation = None;
 
-        cx.shared.volume_manager.lock(|vm| {
+        let (volume, dir, file, protections) = volume_manager.lock(|vm| {
             let mut sd_volume = vm.open_volume(sdmmc::VolumeIdx(0)).unwrap();
+            let protections = load_protections(&mut sd_volume.open_root_dir().unwrap());
 
-            let (file, mut dir) = cx.shared.state_manager.lock(|sm| {
-                let mut dir = sd_volume.open_root_dir().unwrap();
+            let mut dir = sd_volume.open_root_dir().unwrap();
+            let file = state_manager.lock(|sm| {
                 let (file, path) = sm
                     .state
                     .dir_path
@@ -644,35 +915,76 @@ mod app {
                     dir.change_dir(dir_name)
                         .expect("Path only contains existing items.");
                 }
-
-                (file.clone(), dir)
+                file.clone()
             });
 
-            let mut get_running = || cx.shared.state_manager.lock(|sm| sm.state.running); // Function alias for code readability
-
-            while emission_count.unwrap_or(u8::MAX) > 0 && get_running() {
-                let logs = CanLogsIterator::new(
-                    dir.open_file_in_dir(&file, sdmmc::Mode::ReadOnly)
-                        .expect("Path only contains existing items."),
-                );
+            // Kept open through raw handles, the emission running outside of the lock
+            let dir = dir.to_raw_directory();
+            (sd_volume.to_raw_volume(), dir, file, protections)
+        });
 
-                for frame in logs {
-                    while !tx_queue.ready() && get_running() {}
-                    if !get_running() {
-                        break;
+        let mut emitter = Emitter {
+            can,
+            rx_queue: can_rx_consumer,
+            state_manager,
+            tx_queue: cx.local.can_tx_producer,
+            dir,
+            protections: Protections::new(),
+            runs,
+            bitrate,
+            emission_mode,
+            id_filter,
+            range,
+        };
+        let error = match protections {
+            Ok(protections) => {
+                emitter.protections = protections;
+                let vm = &mut volume_manager;
+                let result = match (file.extension(), replay_mode, bisect.as_mut()) {
+                    (_, ReplayMode::Validate, _) => emitter
+                        .validate(vm, &file)
+                        .await
+                        .map(|frames| validation = frames),
+                    (b"SCH", _, _) => emitter.run_schedule(vm, &file).await,
+                    (b"LST", _, _) => emitter.play_list(vm, &file).await,
+                    (_, _, Some(bisect)) => emitter.bisect(vm, &file, bisect).await,
+                    (_, ReplayMode::Step, None) => emitter
+                        .step(vm, &file, step_index)
+                        .await
+                        .map(|sent| step_sent = sent),
+                    (_, replay_mode, None) => {
+                        emitter
+                            .replay(vm, &file, replay_mode == ReplayMode::Record)
+                            .await
                     }
-                    enqueue_frame(tx_queue, frame).expect("tx_queue is ready.");
-                }
-
-                if let Some(ref mut n) = emission_count {
-                    *n -= 1;
-                }
+                };
+                emitter.flush().await; // Wait here for queue to be empty to prevent early `running = false`
+                result.err()
             }
+            Err(error) => Some(error),
+        };
 
-            while tx_queue.len() != 0 && get_running() {} // Wait here for queue to be empty to prevent early `running = false`
+        volume_manager.lock(|vm| {
+            let _ = vm.close_dir(dir);
+            let _ = vm.close_volume(volume);
         });
 
-        cx.shared.state_manager.lock(|sm| sm.state.running = false);
+        emitter.state_manager.lock(|sm| {
+            sm.state.running = false;
+            sm.state.paused = false;
+            sm.state.error = error;
+            if sm.state.bisect.is_some() {
+                sm.state.bisect = bisect;
+            }
+            if let Some((index, frame)) = step_sent {
+                sm.state.step_index = index + 1;
+                sm.state.step_sent = Some((index, frame));
+            }
+            if let Some((frames_count, bad_lines)) = validation {
+                sm.state.success_count = frames_count;
+                sm.state.validation = Some(bad_lines);
+            }
+        });
         state_updater::spawn()
             .expect("state_updater should not be running (it has higher priority)");
     }
@@ -681,19 +993,16 @@ mod app {
     ///
     /// When called, it will resolve the path given in [`State::dir_path`] and create a file in the
     /// found folder. It will then wait for frames to be queued in the SD RX Queue. Queued frames
-    /// will then be poped and written on the Micro SD.
+    /// will then be poped and written on the Micro SD, in the format of [`State::capture_format`].
     ///
     /// When [`State::running`] is set to false, the Queue will be emptied and written in the file
     /// before exiting. This is to prevent too many frames from being lost due to slowness of SD
     /// writing compared to CAN reading, making [`sd_writer()`] late compared to [`can_receiver()`].
     #[task(
         priority = 1,
-        shared = [volume_manager, state_manager],
-        local = [can_rx_consumer],
+        shared = [volume_manager, state_manager, can_rx_consumer],
     )]
     async fn sd_writer(mut cx: sd_writer::Context) {
-        let rx_queue = cx.local.can_rx_consumer;
-
         cx.shared.volume_manager.lock(|vm| {
             let mut sd_volume = vm.open_volume(sdmmc::VolumeIdx(0)).unwrap();
 
@@ -707,32 +1016,59 @@ mod app {
                 dir
             });
 
-            let file_name: String<12> =
-                formatted_string(format_args!("{:08}.log", Mono::now().ticks()))
-                    .expect("Formatted args should fit.");
+            let (bitrate, silent, format) = cx.shared.state_manager.lock(|sm| {
+                (
+                    sm.state.bitrate,
+                    sm.state.capture_silent,
+                    sm.state.capture_format,
+                )
+            });
+
+            let file_name: String<12> = formatted_string(format_args!(
+                "{:08}.{}",
+                Mono::now().ticks(),
+                format.extension()
+            ))
+            .expect("Formatted args should fit.");
             let mut logs = dir
                 .open_file_in_dir(&file_name[..], sdmmc::Mode::ReadWriteCreateOrTruncate)
                 .unwrap();
 
             rprintln!("Writing started to '{}'", file_name);
-            let (bitrate, silent) = cx
-                .shared
-                .state_manager
-                .lock(|sm| (sm.state.bitrate, sm.state.capture_silent));
-            let _ = logs.write(
-                formatted_string::<64>(format_args!(
-                    "# Frames captured by CANary - Bitrate: {:4} kbps, Silent: {}\n",
-                    bitrate as u32 / 1000,
-                    silent
-                ))
-                .expect("Formatted args should fit.")
-                .as_bytes(),
-            );
+            let _ = match format {
+                LogFormat::Log => logs.write(
+                    formatted_string::<64>(format_args!(
+                        "# Frames captured by CANary - Bitrate: {:4} kbps, Silent: {}\n",
+                        bitrate as u32 / 1000,
+                        silent
+                    ))
+                    .expect("Formatted args should fit.")
+                    .as_bytes(),
+                ),
+                LogFormat::Bin => logs.write(&bin_header(bitrate as u32, silent)),
+            };
 
-            while cx.shared.state_manager.lock(|sm| sm.state.running) || rx_queue.ready() {
-                if let Some(frame) = rx_queue.dequeue() {
-                    rprintln!("Writing {:?}", frame);
-                    if logs.write(frame_to_log(&frame).as_bytes()).is_err() {
+            while cx.shared.state_manager.lock(|sm| sm.state.running)
+                || cx.shared.can_rx_consumer.lock(|rx_queue| rx_queue.ready())
+            {
+                if let Some(bus_frame) = cx
+                    .shared
+                    .can_rx_consumer
+                    .lock(|rx_queue| rx_queue.dequeue())
+                {
+                    rprintln!("Writing {:?}", bus_frame);
+                    let written = match format {
+                        LogFormat::Log => logs.write(
+                            frame_to_log(&bus_frame.frame, bus_frame.ticks, bus_frame.transmitted)
+                                .as_bytes(),
+                        ),
+                        LogFormat::Bin => logs.write(&frame_to_bin(
+                            &bus_frame.frame,
+                            bus_frame.ticks,
+                            bus_frame.transmitted,
+                        )),
+                    };
+                    if written.is_err() {
                         rprintln!("Got error on writing ");
                     } else {
                         cx.shared
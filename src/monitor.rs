@@ -0,0 +1,286 @@
+//! Live CAN traffic monitoring abstractions.
+
+use bxcan::{Frame, Id};
+use heapless::{HistoryBuffer, String, Vec};
+
+use crate::{
+    app::{
+        LIVE_FRAMES_AMOUNT, MAX_DBC_SIGNALS, MAX_SNIFFED_IDS, PLOT_SAMPLES_AMOUNT,
+        SNIFFER_HIGHLIGHT_MS,
+    },
+    render::formatted_string,
+    signal::{DbcSignal, Signal},
+};
+
+/// A live monitor of the CAN traffic.
+///
+/// It keeps track of the last frames received (during capture) or sent (during emission) so that
+/// they can be displayed on the live pages of the [`Capture`][crate::state::Screen::Capture] and
+/// [`Emission`][crate::state::Screen::Emission] screens.
+///
+/// It is a shared resource of its own, holding everything the CAN tasks need, so that they never
+/// wait for the [`StateManager`][crate::state::StateManager] while it renders.
+pub struct FrameMonitor {
+    /// Number of frames sent or received by the CAN tasks, not yet added to
+    /// [`State::success_count`][crate::state::State::success_count].
+    pub frames_count: u32,
+    /// Whether recording is suspended, mirrors
+    /// [`State::live_frozen`][crate::state::State::live_frozen].
+    pub frozen: bool,
+    /// Whether the frames sent and received are queued in the CAN RX queue for a transcript.
+    pub transcript: bool,
+    /// Whether the frames received are queued in the CAN RX queue for a logged capture.
+    pub logging: bool,
+    /// The last [`LIVE_FRAMES_AMOUNT`] frames recorded, oldest first.
+    recent_frames: HistoryBuffer<Frame, LIVE_FRAMES_AMOUNT>,
+    /// Statistics of every identifier seen, sorted by identifier.
+    ///
+    /// Only the first [`MAX_SNIFFED_IDS`] identifiers seen are tracked.
+    sniffed_ids: Vec<SniffedId, MAX_SNIFFED_IDS>,
+    /// Signals loaded from the DBC file, offered on the plot page.
+    dbc_signals: Vec<DbcSignal, MAX_DBC_SIGNALS>,
+    /// Signal being plotted, if any.
+    plot: Option<Plot>,
+}
+
+impl FrameMonitor {
+    /// Creates a new empty [`FrameMonitor`].
+    pub const fn new() -> Self {
+        Self {
+            frames_count: 0,
+            frozen: false,
+            transcript: false,
+            logging: false,
+            recent_frames: HistoryBuffer::new(),
+            sniffed_ids: Vec::new(),
+            dbc_signals: Vec::new(),
+            plot: None,
+        }
+    }
+
+    /// Sets the signals loaded from the DBC file.
+    pub fn set_dbc_signals(&mut self, dbc_signals: Vec<DbcSignal, MAX_DBC_SIGNALS>) {
+        self.dbc_signals = dbc_signals;
+    }
+
+    /// Records a `frame` seen at `time_ms` into the monitor.
+    ///
+    /// The oldest recent frame is dropped if the monitor is full. Frames with an identifier not
+    /// yet seen are ignored by the sniffer table if it is full.
+    pub fn record(&mut self, frame: &Frame, time_ms: u32) {
+        self.recent_frames.write(frame.clone());
+
+        if let Some(plot) = self.plot.as_mut().filter(|p| p.id == frame.id()) {
+            if let Some(value) = plot.signal.decode(frame) {
+                plot.samples.write(value);
+            }
+        }
+
+        match self
+            .sniffed_ids
+            .binary_search_by_key(&id_sort_key(frame.id()), |s| id_sort_key(s.frame.id()))
+        {
+            Ok(i) => self.sniffed_ids[i].update(frame, time_ms),
+            Err(i) => {
+                let _ = self.sniffed_ids.insert(i, SniffedId::new(frame, time_ms));
+            }
+        }
+    }
+
+    /// Clears all recorded frames and statistics, and stops plotting.
+    pub fn clear(&mut self) {
+        self.recent_frames.clear();
+        self.sniffed_ids.clear();
+        self.plot = None;
+    }
+
+    /// Returns an iterator over the recorded frames, from the oldest to the most recent.
+    pub fn recent_frames(&self) -> impl Iterator<Item = &Frame> {
+        self.recent_frames.oldest_ordered()
+    }
+
+    /// Returns the statistics of the identifiers seen, sorted by identifier.
+    pub fn sniffed_ids(&self) -> &[SniffedId] {
+        &self.sniffed_ids
+    }
+
+    /// Returns the signal being plotted, if any.
+    pub fn plot(&self) -> Option<&Plot> {
+        self.plot.as_ref()
+    }
+
+    /// Starts plotting the `index`th signal available for the identifier `id`.
+    ///
+    /// Available signals are the DBC signals of `id`, followed by the fields of `length` bits
+    /// starting at each bit of the payload, decoded like little-endian unsigned DBC signals. The
+    /// `index` and `length` are clamped to the available signals and returned. Samples are kept if
+    /// the selected signal didn't change.
+    pub fn select_plot(&mut self, id: Id, index: usize, length: usize) -> (usize, usize) {
+        let dbc_signals = || self.dbc_signals.iter().filter(move |s| s.id == id);
+        let dbc_amount = dbc_signals().count();
+        let length = length.clamp(1, 64);
+        let index = index.min(dbc_amount + 64 - length);
+        // The length only defines the bits fields
+        let key = (index, if index < dbc_amount { 0 } else { length });
+
+        if self
+            .plot
+            .as_ref()
+            .is_some_and(|p| p.id == id && p.key == key)
+        {
+            return (index, length);
+        }
+
+        let (name, signal) = match index.checked_sub(dbc_amount) {
+            None => {
+                let dbc_signal = dbc_signals()
+                    .nth(index)
+                    .expect("Index should be lower than the DBC signals amount.");
+                (dbc_signal.name.clone(), dbc_signal.signal)
+            }
+            Some(start_bit) => (
+                formatted_string(format_args!("Bits {}|{}", start_bit, length))
+                    .expect("Formatted args should fit."),
+                Signal::bits(start_bit as u8, length as u8),
+            ),
+        };
+
+        self.plot = Some(Plot {
+            id,
+            key,
+            name,
+            signal,
+            samples: HistoryBuffer::new(),
+        });
+
+        (index, length)
+    }
+
+    /// Stops plotting, dropping the recorded samples.
+    pub fn stop_plot(&mut self) {
+        self.plot = None;
+    }
+}
+
+/// A signal plotted on the plot page, with its last values.
+pub struct Plot {
+    /// Identifier of the frames containing the signal.
+    pub id: Id,
+    /// Index of the signal among the ones available for [`id`][Plot::id], with the length of the
+    /// bits fields (`0` for DBC signals).
+    key: (usize, usize),
+    /// Name of the signal, displayed in the header of the plot page.
+    pub name: String<16>,
+    /// Definition of the signal.
+    signal: Signal,
+    /// The last [`PLOT_SAMPLES_AMOUNT`] values of the signal, in hundredths.
+    pub samples: HistoryBuffer<i32, PLOT_SAMPLES_AMOUNT>,
+}
+
+/// Returns a key sorting standard identifiers before extended ones, then by value.
+fn id_sort_key(id: Id) -> u32 {
+    match id {
+        Id::Standard(id) => id.as_raw() as u32,
+        Id::Extended(id) => id.as_raw() | 0x8000_0000,
+    }
+}
+
+/// Statistics of a CAN identifier, as displayed by the sniffer page.
+pub struct SniffedId {
+    /// Last frame seen with this identifier.
+    pub frame: Frame,
+    /// Number of frames seen with this identifier.
+    pub count: u32,
+    /// Average period between two frames, in sixteenths of milliseconds.
+    period_x16: u32,
+    /// Average deviation of the period, in sixteenths of milliseconds.
+    jitter_x16: u32,
+    /// Time at which the last frame was seen, in milliseconds.
+    last_time_ms: u32,
+    /// Time at which each byte of the payload last changed, in milliseconds.
+    ///
+    /// A byte that was never seen changing is set to [`SNIFFER_HIGHLIGHT_MS`] before the first
+    /// frame, so that it isn't highlighted.
+    changed_at_ms: [u32; 8],
+}
+
+impl SniffedId {
+    /// Creates the statistics of a new identifier from its first `frame` seen at `time_ms`.
+    fn new(frame: &Frame, time_ms: u32) -> Self {
+        Self {
+            frame: frame.clone(),
+            count: 1,
+            period_x16: 0,
+            jitter_x16: 0,
+            last_time_ms: time_ms,
+            changed_at_ms: [time_ms.wrapping_sub(SNIFFER_HIGHLIGHT_MS); 8],
+        }
+    }
+
+    /// Updates the statistics with a new `frame` seen at `time_ms`.
+    ///
+    /// Period and jitter are computed as exponential moving averages (see [`moving_average()`]).
+    fn update(&mut self, frame: &Frame, time_ms: u32) {
+        let delta_x16 = time_ms.wrapping_sub(self.last_time_ms).saturating_mul(16);
+        if self.count == 1 {
+            self.period_x16 = delta_x16;
+        } else {
+            let deviation_x16 = delta_x16.abs_diff(self.period_x16);
+            self.jitter_x16 = moving_average(self.jitter_x16, deviation_x16);
+            self.period_x16 = moving_average(self.period_x16, delta_x16);
+        }
+
+        let old_data = self.frame.data().map(|d| d.as_ref()).unwrap_or(&[]);
+        let new_data = frame.data().map(|d| d.as_ref()).unwrap_or(&[]);
+        for (i, changed_at) in self.changed_at_ms.iter_mut().enumerate() {
+            if old_data.get(i) != new_data.get(i) {
+                *changed_at = time_ms;
+            }
+        }
+
+        self.frame = frame.clone();
+        self.count = self.count.saturating_add(1);
+        self.last_time_ms = time_ms;
+    }
+
+    /// Returns the average period between two frames, in milliseconds.
+    pub fn period_ms(&self) -> u32 {
+        (self.period_x16 + 8) / 16
+    }
+
+    /// Returns the average deviation of the period, in milliseconds.
+    pub fn jitter_ms(&self) -> u32 {
+        (self.jitter_x16 + 8) / 16
+    }
+
+    /// Returns a bit mask of the payload bytes that changed in the last
+    /// [`SNIFFER_HIGHLIGHT_MS`] before `now_ms`.
+    ///
+    /// Bit `n` is set if byte `n` changed recently.
+    pub fn recently_changed(&self, now_ms: u32) -> u8 {
+        self.changed_at_ms
+            .iter()
+            .enumerate()
+            .filter(|(_, &t)| now_ms.wrapping_sub(t) < SNIFFER_HIGHLIGHT_MS)
+            .fold(0, |mask, (i, _)| mask | 1 << i)
+    }
+}
+
+/// Returns the exponential moving `average` updated with a new `value`, both in fixed point.
+///
+/// The new value has a weight of 1/8, which smooths the average enough to be readable on the
+/// display. The fixed point keeps the rounding error below the unit once scaled back.
+const fn moving_average(average: u32, value: u32) -> u32 {
+    (average as i64 + (value as i64 - average as i64) / 8) as u32
+}
+
+// A constant 10 ms period converges to 10 ms, whatever the first period seen.
+const _: () = {
+    let mut period_x16 = 100 * 16;
+    let mut i = 0;
+    while i < 64 {
+        period_x16 = moving_average(period_x16, 10 * 16);
+        i += 1;
+    }
+    assert!((period_x16 + 8) / 16 == 10);
+};
@@ -0,0 +1,110 @@
+//! Emission playlists abstractions.
+
+use core::str::FromStr;
+
+use embedded_sdmmc::ShortFileName;
+use heapless::Vec;
+
+use crate::{app::MAX_PLAYLIST_ENTRIES, can::Bitrate};
+
+/// A log file emitted by a [`Playlist`].
+#[derive(Debug)]
+pub struct PlaylistEntry {
+    /// Name of the log file, in the directory of the playlist.
+    pub file_name: ShortFileName,
+    /// Number of times the log file is emitted in a row.
+    pub count: u8,
+    /// Delay between the end of the emission of the log file and the next entry, in milliseconds.
+    pub delay_ms: u32,
+    /// Bitrate the log file is emitted at, the one of the emission settings if [`None`].
+    pub bitrate: Option<Bitrate>,
+    /// Number of the line of the `.LST` file listing the log file, for error messages.
+    pub line_number: u32,
+}
+
+/// A sequence of log files emitted one after another, read from a `.LST` file.
+///
+/// The file contains one log file per line, lines starting with `#` are comments. Repeat counts,
+/// delays (in milliseconds) and bitrates (in kbps) are decimal and optional :
+/// ```text
+/// WAKEUP.LOG              # emit WAKEUP.LOG once
+/// ACTION.LOG 3 500        # emit ACTION.LOG 3 times, then wait 500 ms
+/// SLEEP.LOG 1 0 250       # emit SLEEP.LOG once at 250 kbps
+/// ```
+///
+/// Log files are looked for in the directory of the playlist, with their own rewrite rules.
+#[derive(Debug)]
+pub struct Playlist {
+    /// Log files emitted, in order.
+    entries: Vec<PlaylistEntry, MAX_PLAYLIST_ENTRIES>,
+}
+
+impl Playlist {
+    /// Creates a new empty [`Playlist`].
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Parses the line `line_number` of a `.LST` file into `self`.
+    ///
+    /// Empty and comment lines are ignored. Returns a short error message if the line is invalid.
+    pub fn parse_line(&mut self, line: &str, line_number: u32) -> Result<(), &'static str> {
+        let mut fields = line.split('#').next().unwrap_or("").split_whitespace();
+        let Some(file_name) = fields.next() else {
+            return Ok(());
+        };
+
+        let file_name = ShortFileName::create_from_str(file_name).map_err(|_| "Bad name")?;
+        let count = match fields.next() {
+            Some(count) => u8::from_str(count)
+                .ok()
+                .filter(|c| *c > 0)
+                .ok_or("Bad count")?,
+            None => 1,
+        };
+        let delay_ms = match fields.next() {
+            Some(delay) => u32::from_str(delay).map_err(|_| "Bad delay")?,
+            None => 0,
+        };
+        let bitrate = match fields.next() {
+            Some(kbps) => Some(
+                u32::from_str(kbps)
+                    .ok()
+                    .and_then(Bitrate::from_kbps)
+                    .ok_or("Bad bitrate")?,
+            ),
+            None => None,
+        };
+        if fields.next().is_some() {
+            return Err("Extra field");
+        }
+
+        self.entries
+            .push(PlaylistEntry {
+                file_name,
+                count,
+                delay_ms,
+                bitrate,
+                line_number,
+            })
+            .map_err(|_| "Too many")
+    }
+
+    /// Checks that the playlist can be emitted.
+    ///
+    /// Returns a short error message if no log file was listed.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.entries.is_empty() {
+            Err("No entry")
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the log files of the playlist, in order.
+    pub fn entries(&self) -> &[PlaylistEntry] {
+        &self.entries
+    }
+}
@@ -0,0 +1,112 @@
+//! Log ranges abstractions, replaying only part of a log file.
+
+use core::cmp::Ordering;
+
+/// Largest value of a range bound, so that bounds fit on the settings screen.
+pub const MAX_BOUND: u32 = 90_000_000;
+
+/// Unit of the bounds of a [`LogRange`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RangeUnit {
+    /// The whole log file is replayed, bounds are ignored.
+    All,
+    /// Bounds are indexes of frames in the log file, starting at `0`.
+    Index,
+    /// Bounds are times in seconds, relative to the first frame of the log file.
+    Time,
+}
+
+impl RangeUnit {
+    /// Increments `self` to next [`RangeUnit`], wrapping around.
+    pub fn increment(&mut self) {
+        *self = match self {
+            Self::All => Self::Index,
+            Self::Index => Self::Time,
+            Self::Time => Self::All,
+        }
+    }
+
+    /// Decrements `self` to previous [`RangeUnit`], wrapping around.
+    pub fn decrement(&mut self) {
+        *self = match self {
+            Self::Index => Self::All,
+            Self::Time => Self::Index,
+            Self::All => Self::Time,
+        }
+    }
+}
+
+/// Part of a log file replayed by an emission.
+///
+/// Both bounds are inclusive, an end bound of `0` standing for the end of the log file.
+#[derive(Clone, Copy, Debug)]
+pub struct LogRange {
+    /// Unit of the bounds.
+    pub unit: RangeUnit,
+    /// First frame index or time replayed.
+    pub from: u32,
+    /// Last frame index or time replayed, `0` for the end of the log file.
+    pub to: u32,
+}
+
+impl LogRange {
+    /// Creates a new [`LogRange`] covering the whole log file.
+    pub fn new() -> Self {
+        Self {
+            unit: RangeUnit::All,
+            from: 0,
+            to: 0,
+        }
+    }
+
+    /// Returns whether the whole log file is covered.
+    pub fn is_all(&self) -> bool {
+        self.unit == RangeUnit::All
+    }
+
+    /// Returns the bound of a frame given its `index` and its `time` relative to the first frame.
+    fn key(&self, index: u32, time: u32) -> u32 {
+        match self.unit {
+            RangeUnit::Index => index,
+            RangeUnit::All | RangeUnit::Time => time,
+        }
+    }
+
+    /// Returns whether a frame given its `index` and `time` is before the range (`Less`), in it
+    /// (`Equal`) or after it (`Greater`).
+    pub fn position(&self, index: u32, time: u32) -> Ordering {
+        let key = self.key(index, time);
+        if self.is_all() {
+            Ordering::Equal
+        } else if key < self.from {
+            Ordering::Less
+        } else if self.to != 0 && key > self.to {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+
+    /// Steps the [`from`][Self::from] bound, or the [`to`][Self::to] one if `is_to` is set, by
+    /// `step`, up or down.
+    ///
+    /// Bounds stop at [`MAX_BOUND`].
+    pub fn step_bound(&mut self, is_to: bool, step: u32, up: bool) {
+        let bound = if is_to { &mut self.to } else { &mut self.from };
+        *bound = if up {
+            bound.saturating_add(step).min(MAX_BOUND)
+        } else {
+            bound.saturating_sub(step)
+        };
+    }
+
+    /// Returns whether the replay can start from a frame given its `index` and `time`, without
+    /// skipping frames of the range.
+    pub fn can_start_at(&self, index: u32, time: u32) -> bool {
+        match self.unit {
+            RangeUnit::All => index == 0,
+            RangeUnit::Index => index <= self.from,
+            RangeUnit::Time => index == 0 || time < self.from, // Frames of a same second can span
+        }
+    }
+}
@@ -5,17 +5,18 @@ use core::{
     str::FromStr,
 };
 
+use bxcan::{Frame, Id};
 use embedded_graphics::{
     geometry::AnchorX,
     image::Image,
     mono_font::{
-        ascii::{FONT_5X7, FONT_6X12, FONT_6X13_BOLD},
+        ascii::{FONT_4X6, FONT_5X7, FONT_6X12, FONT_6X13_BOLD},
         MonoTextStyle, MonoTextStyleBuilder,
     },
     pixelcolor::BinaryColor,
     prelude::*,
     primitives::{
-        CornerRadii, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, RoundedRectangle,
+        CornerRadii, Line, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, RoundedRectangle,
         StrokeAlignment, StyledDrawable,
     },
     text::{Alignment, Baseline, Text, TextStyle, TextStyleBuilder},
@@ -33,8 +34,13 @@ use stm32f1xx_hal::{
 use tinybmp::Bmp;
 
 use crate::{
+    bisect::Bisect,
     can::{Bitrate, EmissionMode},
-    state::{EmissionSettingsItem, HomeItem},
+    filter::{FilterMode, IdFilter},
+    monitor::{Plot, SniffedId},
+    range::{LogRange, RangeUnit},
+    sd::{BadLines, LogFormat},
+    state::{CaptureSettingsItem, EmissionSettingsItem, FilterSettingsItem, HomeItem, ReplayMode},
 };
 
 /// Type alias for the display driver
@@ -58,8 +64,10 @@ const DISPLAY_WIDTH: u32 = 128;
 const DISPLAY_HEIGHT: u32 = 64;
 
 /// Bottom Y coordinate of first text line
+#[allow(clippy::identity_op)]
 pub const TEXT_LINE_1: i32 = 1 * 12 - 1;
 /// Bottom Y coordinate of second text line
+#[allow(clippy::identity_op)]
 pub const TEXT_LINE_2: i32 = 2 * 12 + 0;
 /// Bottom Y coordinate of third text line
 pub const TEXT_LINE_3: i32 = 3 * 12 + 1;
@@ -99,6 +107,18 @@ static SMALL_TEXT_STYLE: MonoTextStyle<BinaryColor> = MonoTextStyleBuilder::new(
     .text_color(BinaryColor::On)
     .background_color(BinaryColor::Off)
     .build();
+/// Tiny text style (4x6 instead of 6x12)
+static TINY_TEXT_STYLE: MonoTextStyle<BinaryColor> = MonoTextStyleBuilder::new()
+    .font(&FONT_4X6)
+    .text_color(BinaryColor::On)
+    .background_color(BinaryColor::Off)
+    .build();
+/// Inverted tiny text style (color-inverted 4x6)
+static INVERTED_TINY_TEXT_STYLE: MonoTextStyle<BinaryColor> = MonoTextStyleBuilder::new()
+    .font(&FONT_4X6)
+    .text_color(BinaryColor::Off)
+    .background_color(BinaryColor::On)
+    .build();
 
 /// Center-middle text alignement
 static CENTER_MIDDLE: TextStyle = TextStyleBuilder::new()
@@ -152,6 +172,21 @@ pub fn formatted_string<const N: usize>(
     Ok(string)
 }
 
+/// A value in hundredths displayed with two decimals.
+pub struct TwoDecimals(pub i32);
+
+impl core::fmt::Display for TwoDecimals {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{}{}.{:02}",
+            if self.0 < 0 { "-" } else { "" },
+            self.0.unsigned_abs() / 100,
+            self.0.unsigned_abs() % 100
+        )
+    }
+}
+
 /// Takes ownership of a [`String`], makes its ASCII characters lowercase and returns ownership
 /// to it.
 ///
@@ -439,14 +474,21 @@ pub fn draw_file_selection(
 /// Draws the emission screen to the provided `display`.
 ///
 /// Other parameters are from the current [`State`][crate::state::State] and used for display.
+#[allow(clippy::too_many_arguments)]
 pub fn draw_emission(
     display: &mut Display,
     selected: &ShortFileName,
     running: bool,
+    paused: bool,
     count: u8,
     bitrate: &Bitrate,
     mode: &EmissionMode,
     success_count: u32,
+    error: Option<(&str, u32)>,
+    bisect: Option<&Bisect>,
+    step_index: Option<u32>,
+    step_sent: Option<&(u32, Frame)>,
+    validation: Option<&BadLines>,
 ) {
     // Load icons
     let emit_icon = Bmp::<BinaryColor>::from_slice(include_bytes!("./icons/emit.bmp"))
@@ -474,7 +516,9 @@ pub fn draw_emission(
 
     // Draw hints
     if running {
-        draw_center_hint(display, "Stop", -4);
+        draw_left_hint(display, "Stop");
+        draw_center_hint(display, if paused { "Resume" } else { "Pause" }, -4);
+        draw_right_hint(display, "Live");
     } else {
         draw_left_hint(display, "Exit");
         draw_center_hint(display, "Start", -7);
@@ -482,7 +526,21 @@ pub fn draw_emission(
     }
 
     // Load emission parameters
-    let count_str: String<14> = if count == 0 {
+    let tested = bisect.and_then(|b| b.result().or_else(|| b.tested()));
+    let count_str: String<14> = if validation.is_some() {
+        formatted_string(format_args!("Frames: {}", success_count % 10000))
+            .expect("Formatted args should fit.")
+    } else if let Some(index) = step_index {
+        formatted_string(format_args!("Next #{}", index % 10000))
+            .expect("Formatted args should fit.")
+    } else if let Some(tested) = tested {
+        formatted_string(format_args!(
+            "Test {}-{}",
+            tested.start % 10000,
+            tested.end % 10000
+        ))
+        .expect("Formatted args should fit.")
+    } else if count == 0 {
         String::from_str("Repeating xINF").expect("String fits.")
     } else {
         formatted_string(format_args!("Repeating x{:->3}", count))
@@ -491,8 +549,14 @@ pub fn draw_emission(
     let bitrate_str: String<17> =
         formatted_string(format_args!("Bitrate: {:4}kbps", *bitrate as u32 / 1000))
             .expect("Formatted args should fit.");
-    let mode_str: String<15> =
-        formatted_string(format_args!("Mode: {:?}", mode)).expect("Formatted args should fit.");
+    let mode_str: String<15> = match (step_sent.filter(|_| step_index.is_some()), validation) {
+        (Some((index, _)), _) => formatted_string(format_args!("Sent #{}", index % 10000)),
+        (None, Some(bad_lines)) => {
+            formatted_string(format_args!("Bad lines: {}", bad_lines.count % 10000))
+        }
+        (None, None) => formatted_string(format_args!("Mode: {:?}", mode)),
+    }
+    .expect("Formatted args should fit.");
 
     // Draw emission parameters
     let _ = Image::new(&scroll_icon, Point::new(5 * 14 - 2, TEXT_LINE_2 - 10)).draw(display);
@@ -517,12 +581,36 @@ pub fn draw_emission(
         LEFT_BOTTOM,
     )
     .draw(display);
+    if let Some((_, frame)) = step_sent.filter(|_| step_index.is_some()) {
+        let _ = Text::with_text_style(
+            &format_frame_line(frame),
+            Point::new(0, TEXT_LINE_4 + 1),
+            TINY_TEXT_STYLE,
+            LEFT_BOTTOM,
+        )
+        .draw(display);
+    } else if let Some(bad_lines) = validation.filter(|b| b.count > 0) {
+        let mut numbers_str: String<32> = String::new();
+        for number in bad_lines.first.iter() {
+            let _ = write!(numbers_str, "#{} ", number % 100_000);
+        }
+        if bad_lines.count as usize > bad_lines.first.len() {
+            let _ = numbers_str.push_str("...");
+        }
+        let _ = Text::with_text_style(
+            &numbers_str,
+            Point::new(0, TEXT_LINE_4 + 1),
+            TINY_TEXT_STYLE,
+            LEFT_BOTTOM,
+        )
+        .draw(display);
+    }
 
     // Draw emission state icon
     let _ = Image::new(
-        if running {
+        if running && !paused {
             &pause_icon
-        } else if success_count == 0 {
+        } else if running || success_count == 0 {
             &play_icon
         } else {
             &stop_icon
@@ -532,8 +620,28 @@ pub fn draw_emission(
     .draw(display);
 
     // Draw emission state text
-    let state_str: String<16> = if running {
+    let state_str: String<24> = if running && paused {
+        String::from_str("Paused").expect("String fits.")
+    } else if running {
         String::from_str("Running").expect("String fits.")
+    } else if let Some((message, line)) = error {
+        match line {
+            0 => formatted_string(format_args!("Error\n{}", message)),
+            n => formatted_string(format_args!("Line {}\n{}", n % 10000, message)),
+        }
+        .expect("Formatted args should fit.")
+    } else if let Some(bisect) = bisect.filter(|b| b.is_saved() || b.is_awaiting_answer()) {
+        if bisect.is_saved() {
+            String::from_str("Saved\nresult").expect("String fits.")
+        } else {
+            String::from_str("Effect?\nUp/Down").expect("String fits.")
+        }
+    } else if let Some(bad_lines) = validation {
+        match bad_lines.count {
+            0 => String::from_str("Valid\nfile").expect("String fits."),
+            n => formatted_string(format_args!("{} bad\nlines", n % 10000))
+                .expect("Formatted args should fit."),
+        }
     } else if success_count == 0 {
         String::from_str("Standby").expect("String fits.")
     } else {
@@ -558,6 +666,7 @@ pub fn draw_capture(
     running: bool,
     bitrate: &Bitrate,
     silent: bool,
+    logging: bool,
     success_count: u32,
 ) {
     // Load icons
@@ -588,6 +697,7 @@ pub fn draw_capture(
     // Draw hints
     if running {
         draw_center_hint(display, "Stop", -4);
+        draw_right_hint(display, "Live");
     } else {
         draw_left_hint(display, "Exit");
         draw_center_hint(display, "Start", -7);
@@ -602,6 +712,8 @@ pub fn draw_capture(
     .expect("Formatted args should fit.");
     let silent_str: String<13> =
         formatted_string(format_args!("Silent: {:}", silent)).expect("Formatted args should fit.");
+    let logging_str: String<14> = formatted_string(format_args!("Logging: {:}", logging))
+        .expect("Formatted args should fit.");
 
     // Draw capture settings
     let _ = Image::new(&scroll_icon, Point::new(5 * 11 - 2, TEXT_LINE_2 - 3)).draw(display);
@@ -619,6 +731,13 @@ pub fn draw_capture(
         LEFT_BOTTOM,
     )
     .draw(display);
+    let _ = Text::with_text_style(
+        &logging_str,
+        Point::new(0, TEXT_LINE_3 + 5 + 8),
+        SMALL_TEXT_STYLE,
+        LEFT_BOTTOM,
+    )
+    .draw(display);
 
     // Draw capture state icon
     let _ = Image::new(
@@ -638,9 +757,12 @@ pub fn draw_capture(
         String::from_str("Listening").expect("String fits.")
     } else if success_count == 0 {
         String::from_str("Standby").expect("String fits.")
-    } else {
+    } else if logging {
         formatted_string(format_args!("Saved {}\nframes", success_count % 10000))
             .expect("Formatted args should fit.")
+    } else {
+        formatted_string(format_args!("Seen {}\nframes", success_count % 10000))
+            .expect("Formatted args should fit.")
     };
     let _ = Text::with_text_style(
         &state_str,
@@ -657,8 +779,11 @@ pub fn draw_capture(
 pub fn draw_emission_settings(
     display: &mut Display,
     selected_item: &EmissionSettingsItem,
+    step: u32,
     bitrate: &Bitrate,
     mode: &EmissionMode,
+    replay_mode: &ReplayMode,
+    range: &LogRange,
 ) {
     // Load icons
     let emit_icon = Bmp::<BinaryColor>::from_slice(include_bytes!("./icons/emit.bmp"))
@@ -676,44 +801,229 @@ pub fn draw_emission_settings(
     // Center on X axis of parameters display
     let val_center = DISPLAY_WIDTH as i32 - 5 * 6 - 5;
 
-    // Draw Bitrate parameter
-    let _ = Text::with_text_style(
-        "Bitrate:",
-        Point::new(1, TEXT_LINE_2),
-        DEFAULT_TEXT_STYLE,
-        LEFT_BOTTOM,
+    // Format range bounds
+    let format_bound = |bound: u32, is_end: bool| match range.unit {
+        RangeUnit::All => String::from_str("-").map_err(|_| core::fmt::Error),
+        _ if is_end && bound == 0 => String::from_str("End").map_err(|_| core::fmt::Error),
+        RangeUnit::Index => formatted_string::<9>(format_args!("{}", bound)),
+        RangeUnit::Time => formatted_string::<9>(format_args!("{}s", bound)),
+    };
+
+    // Draw parameters, scrolling so that the selected one is visible
+    let first_row = (*selected_item as usize).saturating_sub(2);
+    for (i, (name, value)) in [
+        (
+            "Bitrate:",
+            formatted_string::<9>(format_args!("{}kbps", *bitrate as u32 / 1000)),
+        ),
+        ("Mode:", formatted_string::<9>(format_args!("{:?}", mode))),
+        (
+            "Replay:",
+            formatted_string::<9>(format_args!("{:?}", replay_mode)),
+        ),
+        (
+            "Range:",
+            formatted_string::<9>(format_args!("{:?}", range.unit)),
+        ),
+        ("Step:", formatted_string::<9>(format_args!("x{}", step))),
+        ("From:", format_bound(range.from, false)),
+        ("To:", format_bound(range.to, true)),
+    ]
+    .into_iter()
+    .enumerate()
+    .skip(first_row)
+    .take(3)
+    {
+        let line = [TEXT_LINE_2, TEXT_LINE_3, TEXT_LINE_4][i - first_row];
+        let _ = Text::with_text_style(name, Point::new(1, line), DEFAULT_TEXT_STYLE, LEFT_BOTTOM)
+            .draw(display);
+        let _ = Text::with_text_style(
+            &value.expect("Formatted args should fit."),
+            Point::new(val_center, line),
+            DEFAULT_TEXT_STYLE,
+            CENTER_BOTTOM,
+        )
+        .draw(display);
+    }
+
+    // Draw parameter edition box (box with LEFT and RIGHT hints surrounding selected value)
+    let selected_row = [TEXT_LINE_2, TEXT_LINE_3, TEXT_LINE_4][*selected_item as usize - first_row];
+    let _ = Image::new(
+        &left_icon,
+        Point::new(val_center - 6 * 5 - 2, selected_row - 11),
     )
     .draw(display);
-    let _ = Text::with_text_style(
-        &formatted_string::<9>(format_args!("{}kbps", *bitrate as u32 / 1000))
-            .expect("Formatted args should fit."),
-        Point::new(val_center, TEXT_LINE_2),
-        DEFAULT_TEXT_STYLE,
-        CENTER_BOTTOM,
+    let _ = Image::new(
+        &right_icon,
+        Point::new(val_center + 6 * 4 + 4, selected_row - 11),
     )
     .draw(display);
+    let _ = RoundedRectangle::with_equal_corners(
+        Rectangle::with_center(
+            Point::new(val_center, selected_row - 6),
+            Size::new(6 * 11 + 2, 12),
+        ),
+        Size::new_equal(4),
+    )
+    .draw_styled(&DEFAULT_STROKE, display);
+}
 
-    // Draw mode parameter
-    let _ = Text::with_text_style(
-        "Mode:",
-        Point::new(1, TEXT_LINE_3),
-        DEFAULT_TEXT_STYLE,
-        LEFT_BOTTOM,
+/// Draws the emission filter settings to the provided `display`.
+///
+/// `entry` is the index of the edited range of `filter`, and `step` the value its bounds are
+/// changed by. Other parameters are from the current [`State`][crate::state::State] and used for
+/// display.
+pub fn draw_filter_settings(
+    display: &mut Display,
+    selected_item: &FilterSettingsItem,
+    filter: &IdFilter,
+    entry: usize,
+    step: u32,
+) {
+    // Load icons
+    let emit_icon = Bmp::<BinaryColor>::from_slice(include_bytes!("./icons/emit.bmp"))
+        .expect("Image is a valid BMP.");
+    let right_icon = Bmp::<BinaryColor>::from_slice(include_bytes!("./icons/right.bmp"))
+        .expect("Image is a valid BMP.");
+    let left_icon = Bmp::<BinaryColor>::from_slice(include_bytes!("./icons/left.bmp"))
+        .expect("Image is a valid BMP.");
+
+    // Draw header and hint
+    draw_header(display, "Filter Settings", false);
+    draw_center_hint(display, "Save", 0);
+    let _ = Image::new(&emit_icon, Point::zero()).draw(display);
+
+    // Center on X axis of parameters display
+    let val_center = DISPLAY_WIDTH as i32 - 5 * 6 - 5;
+
+    // Format edited range, ranges being only used by some modes
+    let range = filter.ranges().get(entry);
+    let uses_ranges = matches!(filter.mode, FilterMode::Keep | FilterMode::Drop);
+    let format_id = |id: Option<u32>| match id {
+        Some(id) if uses_ranges && id > 0x7FF => formatted_string::<9>(format_args!("{:08X}", id)),
+        Some(id) if uses_ranges => formatted_string::<9>(format_args!("{:03X}", id)),
+        _ => String::from_str("-").map_err(|_| core::fmt::Error),
+    };
+    let entry_str = match range {
+        _ if !uses_ranges => String::from_str("-").map_err(|_| core::fmt::Error),
+        Some(_) => formatted_string::<9>(format_args!("{}/{}", entry + 1, filter.ranges().len())),
+        None => String::from_str("New").map_err(|_| core::fmt::Error),
+    };
+
+    // Draw parameters, scrolling so that the selected one is visible
+    let first_row = (*selected_item as usize).saturating_sub(2);
+    for (i, (name, value)) in [
+        (
+            "Filter:",
+            formatted_string::<9>(format_args!("{:?}", filter.mode)),
+        ),
+        ("Entry:", entry_str),
+        ("Step:", formatted_string::<9>(format_args!("x{:X}", step))),
+        ("First:", format_id(range.map(|(first, _)| *first))),
+        ("Last:", format_id(range.map(|(_, last)| *last))),
+    ]
+    .into_iter()
+    .enumerate()
+    .skip(first_row)
+    .take(3)
+    {
+        let line = [TEXT_LINE_2, TEXT_LINE_3, TEXT_LINE_4][i - first_row];
+        let _ = Text::with_text_style(name, Point::new(1, line), DEFAULT_TEXT_STYLE, LEFT_BOTTOM)
+            .draw(display);
+        let _ = Text::with_text_style(
+            &value.expect("Formatted args should fit."),
+            Point::new(val_center, line),
+            DEFAULT_TEXT_STYLE,
+            CENTER_BOTTOM,
+        )
+        .draw(display);
+    }
+
+    // Draw parameter edition box (box with LEFT and RIGHT hints surrounding selected value)
+    let selected_row = [TEXT_LINE_2, TEXT_LINE_3, TEXT_LINE_4][*selected_item as usize - first_row];
+    let _ = Image::new(
+        &left_icon,
+        Point::new(val_center - 6 * 5 - 2, selected_row - 11),
     )
     .draw(display);
-    let _ = Text::with_text_style(
-        &formatted_string::<9>(format_args!("{:?}", mode)).expect("Formatted args should fit."),
-        Point::new(val_center, TEXT_LINE_3),
-        DEFAULT_TEXT_STYLE,
-        CENTER_BOTTOM,
+    let _ = Image::new(
+        &right_icon,
+        Point::new(val_center + 6 * 4 + 4, selected_row - 11),
     )
     .draw(display);
+    let _ = RoundedRectangle::with_equal_corners(
+        Rectangle::with_center(
+            Point::new(val_center, selected_row - 6),
+            Size::new(6 * 11 + 2, 12),
+        ),
+        Size::new_equal(4),
+    )
+    .draw_styled(&DEFAULT_STROKE, display);
+}
+
+/// Draws the capture settings to the provided `display`.
+///
+/// Other parameters are from the current [`State`][crate::state::State] and used for display.
+pub fn draw_capture_settings(
+    display: &mut Display,
+    selected_item: &CaptureSettingsItem,
+    bitrate: &Bitrate,
+    silent: bool,
+    logging: bool,
+    format: &LogFormat,
+) {
+    // Load icons
+    let capture_icon = Bmp::<BinaryColor>::from_slice(include_bytes!("./icons/capture.bmp"))
+        .expect("Image is a valid BMP.");
+    let right_icon = Bmp::<BinaryColor>::from_slice(include_bytes!("./icons/right.bmp"))
+        .expect("Image is a valid BMP.");
+    let left_icon = Bmp::<BinaryColor>::from_slice(include_bytes!("./icons/left.bmp"))
+        .expect("Image is a valid BMP.");
+
+    // Draw header and hint
+    draw_header(display, "Capture Settings", false);
+    draw_center_hint(display, "Save", 0);
+    let _ = Image::new(&capture_icon, Point::zero()).draw(display);
+
+    // Center on X axis of parameters display
+    let val_center = DISPLAY_WIDTH as i32 - 5 * 6 - 5;
+
+    // Draw parameters, scrolling so that the selected one is visible
+    let first_row = (*selected_item as usize).saturating_sub(2);
+    for (i, (name, value)) in [
+        (
+            "Bitrate:",
+            formatted_string::<9>(format_args!("{}kbps", *bitrate as u32 / 1000)),
+        ),
+        ("Silent:", formatted_string::<9>(format_args!("{}", silent))),
+        (
+            "Logging:",
+            formatted_string::<9>(format_args!("{}", logging)),
+        ),
+        (
+            "Format:",
+            formatted_string::<9>(format_args!(".{}", format.extension())),
+        ),
+    ]
+    .into_iter()
+    .enumerate()
+    .skip(first_row)
+    .take(3)
+    {
+        let line = [TEXT_LINE_2, TEXT_LINE_3, TEXT_LINE_4][i - first_row];
+        let _ = Text::with_text_style(name, Point::new(1, line), DEFAULT_TEXT_STYLE, LEFT_BOTTOM)
+            .draw(display);
+        let _ = Text::with_text_style(
+            &value.expect("Formatted args should fit."),
+            Point::new(val_center, line),
+            DEFAULT_TEXT_STYLE,
+            CENTER_BOTTOM,
+        )
+        .draw(display);
+    }
 
     // Draw parameter edition box (box with LEFT and RIGHT hints surrounding selected value)
-    let selected_row = match selected_item {
-        EmissionSettingsItem::Bitrate => TEXT_LINE_2,
-        EmissionSettingsItem::Mode => TEXT_LINE_3,
-    };
+    let selected_row = [TEXT_LINE_2, TEXT_LINE_3, TEXT_LINE_4][*selected_item as usize - first_row];
     let _ = Image::new(
         &left_icon,
         Point::new(val_center - 6 * 5 - 2, selected_row - 11),
@@ -733,3 +1043,242 @@ pub fn draw_emission_settings(
     )
     .draw_styled(&DEFAULT_STROKE, display);
 }
+
+/// Formats a [`Frame`] on a single line for the live pages.
+///
+/// Format is as follows (remote frames show `RTR` instead of their payload) :
+/// ```text
+/// 18DAF110 8 0123456789ABCDEF
+/// ^^^^^^^^┤ ┤ ^^^^^^^^^^^^^^^┴─ Hexadecimal frame payload
+///         │ └─ Data Length Code
+///         └─ Hexadecimal identifier, padded to the length of an extended identifier
+/// ```
+pub fn format_frame_line(frame: &Frame) -> String<32> {
+    let mut line: String<32> = match frame.id() {
+        Id::Standard(id) => {
+            formatted_string(format_args!("{:03X}      {}", id.as_raw(), frame.dlc()))
+        }
+        Id::Extended(id) => formatted_string(format_args!("{:08X} {}", id.as_raw(), frame.dlc())),
+    }
+    .expect("Formatted args should fit.");
+
+    match frame.data() {
+        Some(data) => {
+            let _ = line.push(' ');
+            for byte in data.iter() {
+                let _ = write!(line, "{:02X}", byte);
+            }
+        }
+        None => {
+            let _ = line.push_str(" RTR");
+        }
+    }
+
+    line
+}
+
+/// Draws the live frames page to the provided `display`.
+///
+/// The `frames` are drawn from top to bottom, the most recent frame being the last one. The header
+/// tells whether the frames are being sent (`emitting`) or received and if the view is `frozen`.
+pub fn draw_live_frames<'a>(
+    display: &mut Display,
+    emitting: bool,
+    frozen: bool,
+    frames: impl Iterator<Item = &'a Frame>,
+) {
+    // Draw header
+    draw_header(
+        display,
+        match (emitting, frozen) {
+            (true, false) => "Live TX",
+            (true, true) => "Live TX (frozen)",
+            (false, false) => "Live RX",
+            (false, true) => "Live RX (frozen)",
+        },
+        false,
+    );
+
+    // Draw frames
+    for (i, frame) in frames.enumerate() {
+        let _ = Text::with_text_style(
+            &format_frame_line(frame),
+            Point::new(0, TEXT_LINE_1 + 1 + 6 * (i as i32 + 1)),
+            TINY_TEXT_STYLE,
+            LEFT_BOTTOM,
+        )
+        .draw(display);
+    }
+}
+
+/// Draws the sniffer page to the provided `display`.
+///
+/// Each identifier of `sniffed_ids` takes 2 lines: the first one shows the identifier, its
+/// message count, average period and jitter, the second one shows its latest payload. Bytes of the
+/// payload that recently changed (relative to `now_ms`) are drawn color-inverted.
+///
+/// The identifiers are shown by pages of 4, the page displayed being the one containing the
+/// `selected` identifier. If `selected` is out of bounds, the last identifier is selected.
+pub fn draw_sniffer(
+    display: &mut Display,
+    frozen: bool,
+    sniffed_ids: &[SniffedId],
+    selected: usize,
+    now_ms: u32,
+) {
+    let selected = selected.min(sniffed_ids.len().saturating_sub(1));
+
+    // Draw header
+    let header: String<16> = formatted_string(format_args!(
+        "{} {}/{}",
+        if frozen { "Frozen" } else { "Sniffer" },
+        (selected + 1).min(sniffed_ids.len()),
+        sniffed_ids.len(),
+    ))
+    .expect("Formatted args should fit.");
+    draw_header(display, &header, false);
+
+    // Draw selected page
+    let page_start = selected - selected % 4;
+    for (i, sniffed) in sniffed_ids.iter().enumerate().skip(page_start).take(4) {
+        let line = TEXT_LINE_1 + 1 + 12 * (i - page_start) as i32;
+
+        // Identifier and statistics
+        let id_str: String<8> = match sniffed.frame.id() {
+            Id::Standard(id) => formatted_string(format_args!("{:03X}", id.as_raw())),
+            Id::Extended(id) => formatted_string(format_args!("{:08X}", id.as_raw())),
+        }
+        .expect("Formatted args should fit.");
+        let stats_str: String<32> = formatted_string(format_args!(
+            "{}{:<8} n{:<6}T{:<5}J{}",
+            if i == selected { '>' } else { ' ' },
+            id_str,
+            sniffed.count % 1_000_000,
+            sniffed.period_ms().min(99_999),
+            sniffed.jitter_ms().min(9_999),
+        ))
+        .expect("Formatted args should fit.");
+        let _ = Text::with_text_style(
+            &stats_str,
+            Point::new(0, line + 6),
+            TINY_TEXT_STYLE,
+            LEFT_BOTTOM,
+        )
+        .draw(display);
+
+        // Payload with changed bytes highlighted
+        let changed = sniffed.recently_changed(now_ms);
+        match sniffed.frame.data() {
+            Some(data) => {
+                for (n, byte) in data.iter().enumerate() {
+                    let _ = Text::with_text_style(
+                        &formatted_string::<2>(format_args!("{:02X}", byte))
+                            .expect("Formatted args should fit."),
+                        Point::new(4 * 2 + 4 * 3 * n as i32, line + 12),
+                        if changed & 1 << n != 0 {
+                            INVERTED_TINY_TEXT_STYLE
+                        } else {
+                            TINY_TEXT_STYLE
+                        },
+                        LEFT_BOTTOM,
+                    )
+                    .draw(display);
+                }
+            }
+            None => {
+                let _ = Text::with_text_style(
+                    &formatted_string::<8>(format_args!("RTR {}", sniffed.frame.dlc()))
+                        .expect("Formatted args should fit."),
+                    Point::new(4 * 2, line + 12),
+                    TINY_TEXT_STYLE,
+                    LEFT_BOTTOM,
+                )
+                .draw(display);
+            }
+        }
+    }
+}
+
+/// Draws the plot page to the provided `display`.
+///
+/// The samples of the `plot` are drawn as a line graph autoscaled between their minimum and
+/// maximum, the last sample being on the right edge of the display. The last value and the scale
+/// are written below the graph.
+pub fn draw_plot(display: &mut Display, frozen: bool, plot: Option<&Plot>) {
+    /// Top Y coordinate of the graph.
+    const GRAPH_TOP: i32 = TEXT_LINE_1 + 3;
+    /// Bottom Y coordinate of the graph.
+    const GRAPH_BOTTOM: i32 = TEXT_LINE_5 - 9;
+
+    let Some(plot) = plot else {
+        draw_header(display, "Plot", false);
+        let _ = Text::with_text_style(
+            "No ID selected",
+            Point::new((DISPLAY_WIDTH / 2) as i32, TEXT_LINE_3),
+            DEFAULT_TEXT_STYLE,
+            CENTER_BOTTOM,
+        )
+        .draw(display);
+        return;
+    };
+
+    // Draw header
+    let mut header: String<32> = match plot.id {
+        Id::Standard(id) => formatted_string(format_args!("{:03X}", id.as_raw())),
+        Id::Extended(id) => formatted_string(format_args!("{:08X}", id.as_raw())),
+    }
+    .expect("Formatted args should fit.");
+    let _ = write!(header, " {}", plot.name);
+    draw_header(display, &header, false);
+
+    // Draw graph
+    let Some(last) = plot.samples.recent() else {
+        let _ = Text::with_text_style(
+            "No value yet",
+            Point::new((DISPLAY_WIDTH / 2) as i32, TEXT_LINE_3),
+            DEFAULT_TEXT_STYLE,
+            CENTER_BOTTOM,
+        )
+        .draw(display);
+        return;
+    };
+    let (min, max) = plot
+        .samples
+        .iter()
+        .fold((i32::MAX, i32::MIN), |(min, max), v| {
+            (min.min(*v), max.max(*v))
+        });
+    let span = (max as i64 - min as i64).max(1);
+    let to_point = |i: usize, value: i32| {
+        Point::new(
+            (DISPLAY_WIDTH as usize - plot.samples.len() + i) as i32,
+            GRAPH_BOTTOM
+                - ((value as i64 - min as i64) * (GRAPH_BOTTOM - GRAPH_TOP) as i64 / span) as i32,
+        )
+    };
+
+    let mut previous = None;
+    for (i, value) in plot.samples.oldest_ordered().enumerate() {
+        let point = to_point(i, *value);
+        let _ = Line::new(previous.unwrap_or(point), point).draw_styled(&DEFAULT_STROKE, display);
+        previous = Some(point);
+    }
+
+    // Draw value and scale, truncated if too long
+    let mut footer: String<32> = String::new();
+    let _ = write!(
+        footer,
+        "{}{} [{}, {}]",
+        if frozen { "Frozen " } else { "" },
+        TwoDecimals(*last),
+        TwoDecimals(min),
+        TwoDecimals(max)
+    );
+    let _ = Text::with_text_style(
+        &footer,
+        Point::new(0, TEXT_LINE_5),
+        TINY_TEXT_STYLE,
+        LEFT_BOTTOM,
+    )
+    .draw(display);
+}
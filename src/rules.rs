@@ -0,0 +1,167 @@
+//! Frame rewrite rules abstractions.
+
+use bxcan::{Data, Frame, Id};
+
+use crate::{can::parse_id, sd::decode_hex};
+
+/// A rule rewriting frames during emission, read from a `.RUL` file.
+///
+/// The file contains one rule per line, lines starting with `#` are comments. Each rule starts
+/// with an action and the identifier of the frames it applies to (hexadecimal, `*` for any
+/// identifier) :
+/// ```text
+/// map 123 456                             # change identifier 123 into 456
+/// drop 7DF                                # don't send frames with identifier 7DF
+/// set 123 2 0F                            # set the bits 0F of byte 2
+/// clear 123 2 0F                          # clear the bits 0F of byte 2
+/// xor * 0 FF                              # invert the bits FF of byte 0 on every frame
+/// replace 123 0102030405060708 11223344   # replace the payload when it matches
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Rule {
+    /// Identifier of the frames the rule applies to, [`None`] for every frame.
+    id: Option<Id>,
+    /// Rewrite applied to the matching frames.
+    action: RuleAction,
+}
+
+/// Rewrite applied by a [`Rule`].
+#[derive(Clone, Copy, Debug)]
+enum RuleAction {
+    /// Changes the identifier of the frame.
+    Map(Id),
+    /// Drops the frame.
+    Drop,
+    /// Sets the bits of the mask in the byte at the index.
+    Set(usize, u8),
+    /// Clears the bits of the mask in the byte at the index.
+    Clear(usize, u8),
+    /// Inverts the bits of the mask in the byte at the index.
+    Xor(usize, u8),
+    /// Replaces the payload with the second one if it equals the first one.
+    Replace(Data, Data),
+}
+
+impl Rule {
+    /// Parses a line of a `.RUL` file.
+    ///
+    /// Returns [`None`] for empty and comment lines, or a short error message if the line is
+    /// invalid.
+    pub fn parse(line: &str) -> Result<Option<Self>, &'static str> {
+        let mut fields = line.split('#').next().unwrap_or("").split_whitespace();
+        let Some(action) = fields.next() else {
+            return Ok(None);
+        };
+        let id = match fields.next().ok_or("No ID")? {
+            "*" => None,
+            id => Some(parse_id(id).ok_or("Bad ID")?),
+        };
+
+        let action = match action {
+            "map" => RuleAction::Map(fields.next().and_then(parse_id).ok_or("Bad ID")?),
+            "drop" => RuleAction::Drop,
+            "set" => {
+                let (index, mask) = parse_byte_mask(&mut fields)?;
+                RuleAction::Set(index, mask)
+            }
+            "clear" => {
+                let (index, mask) = parse_byte_mask(&mut fields)?;
+                RuleAction::Clear(index, mask)
+            }
+            "xor" => {
+                let (index, mask) = parse_byte_mask(&mut fields)?;
+                RuleAction::Xor(index, mask)
+            }
+            "replace" => RuleAction::Replace(parse_data(&mut fields)?, parse_data(&mut fields)?),
+            _ => return Err("Bad action"),
+        };
+
+        if fields.next().is_some() {
+            return Err("Extra field");
+        }
+
+        Ok(Some(Self { id, action }))
+    }
+
+    /// Applies the rule to `frame`, returning the rewritten frame or [`None`] if it is dropped.
+    ///
+    /// Byte rules don't apply to remote frames nor to bytes beyond the DLC.
+    fn apply(&self, frame: Frame) -> Option<Frame> {
+        if self.id.is_some_and(|id| id != frame.id()) {
+            return Some(frame);
+        }
+
+        let Some(data) = frame.data() else {
+            return match self.action {
+                RuleAction::Map(id) => Some(Frame::new_remote(id, frame.dlc())),
+                RuleAction::Drop => None,
+                _ => Some(frame),
+            };
+        };
+
+        let mut payload = *data;
+        match self.action {
+            RuleAction::Map(id) => return Some(Frame::new_data(id, payload)),
+            RuleAction::Drop => return None,
+            RuleAction::Set(i, mask) => {
+                if let Some(byte) = payload.get_mut(i) {
+                    *byte |= mask
+                }
+            }
+            RuleAction::Clear(i, mask) => {
+                if let Some(byte) = payload.get_mut(i) {
+                    *byte &= !mask
+                }
+            }
+            RuleAction::Xor(i, mask) => {
+                if let Some(byte) = payload.get_mut(i) {
+                    *byte ^= mask
+                }
+            }
+            RuleAction::Replace(old, new) => {
+                if payload == old {
+                    payload = new
+                }
+            }
+        }
+
+        Some(Frame::new_data(frame.id(), payload))
+    }
+}
+
+/// Parses the byte index (decimal) and mask (hexadecimal) fields of a byte rule.
+fn parse_byte_mask<'a>(
+    fields: &mut impl Iterator<Item = &'a str>,
+) -> Result<(usize, u8), &'static str> {
+    let index = fields
+        .next()
+        .and_then(|i| i.parse::<usize>().ok())
+        .filter(|i| *i < 8)
+        .ok_or("Bad index")?;
+    let mask = fields
+        .next()
+        .and_then(|m| u8::from_str_radix(m, 16).ok())
+        .ok_or("Bad mask")?;
+
+    Ok((index, mask))
+}
+
+/// Parses a hexadecimal payload field of a `replace` rule.
+fn parse_data<'a>(fields: &mut impl Iterator<Item = &'a str>) -> Result<Data, &'static str> {
+    fields
+        .next()
+        .and_then(|d| decode_hex(d).ok())
+        .and_then(|d| Data::new(&d))
+        .ok_or("Bad data")
+}
+
+/// Applies every rule of `rules` to `frame` in order, returning the rewritten frame or [`None`] if
+/// it is dropped.
+///
+/// Rules are matched against the frame as rewritten by the previous rules, so a rule following a
+/// `map` rule must use the new identifier.
+pub fn apply_rules(rules: &[Rule], frame: Frame) -> Option<Frame> {
+    rules
+        .iter()
+        .try_fold(frame, |frame, rule| rule.apply(frame))
+}
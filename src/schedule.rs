@@ -0,0 +1,138 @@
+//! Cyclic frames scheduling abstractions.
+
+use core::str::FromStr;
+
+use bxcan::{Data, Frame};
+use heapless::Vec;
+use rtic_monotonics::fugit::{Duration, Instant};
+
+use crate::{
+    app::{MAX_SCHEDULES, TICK_RATE},
+    can::parse_id,
+    sd::decode_hex,
+};
+
+/// A frame sent periodically by a [`Scheduler`].
+#[derive(Debug)]
+struct Schedule {
+    /// Frame to send.
+    frame: Frame,
+    /// Delay between two emissions of the frame.
+    period: Duration<u32, 1, TICK_RATE>,
+    /// Delay between the start of the [`Scheduler`] and the first emission of the frame.
+    offset: Duration<u32, 1, TICK_RATE>,
+    /// Time of the next emission of the frame, set by [`Scheduler::start()`].
+    next: Instant<u32, 1, TICK_RATE>,
+}
+
+/// A scheduler sending frames periodically, read from a `.SCH` file.
+///
+/// The file contains one frame per line, lines starting with `#` are comments. Identifiers and
+/// payloads are hexadecimal, periods and offsets are decimal milliseconds :
+/// ```text
+/// 123 0102030405060708 100    # send this frame every 100 ms
+/// 456 1122 20 5               # send this frame every 20 ms, starting 5 ms after the others
+/// ```
+///
+/// Emission times are computed from the previous emission time rather than from the actual sending
+/// time, so that delays in the TX queue don't accumulate over time.
+#[derive(Debug)]
+pub struct Scheduler {
+    /// Frames sent periodically.
+    schedules: Vec<Schedule, MAX_SCHEDULES>,
+}
+
+impl Scheduler {
+    /// Creates a new [`Scheduler`] without frames.
+    pub fn new() -> Self {
+        Self {
+            schedules: Vec::new(),
+        }
+    }
+
+    /// Parses a line of a `.SCH` file into `self`.
+    ///
+    /// Empty and comment lines are ignored. Returns a short error message if the line is invalid.
+    pub fn parse_line(&mut self, line: &str) -> Result<(), &'static str> {
+        let mut fields = line.split('#').next().unwrap_or("").split_whitespace();
+        let Some(id) = fields.next() else {
+            return Ok(());
+        };
+
+        let id = parse_id(id).ok_or("Bad ID")?;
+        let data = fields
+            .next()
+            .and_then(|d| decode_hex(d).ok())
+            .and_then(|d| Data::new(&d))
+            .ok_or("Bad data")?;
+        let period = fields
+            .next()
+            .and_then(|p| u32::from_str(p).ok())
+            .filter(|p| *p > 0)
+            .ok_or("Bad period")?;
+        let offset = match fields.next() {
+            Some(offset) => u32::from_str(offset).map_err(|_| "Bad offset")?,
+            None => 0,
+        };
+        if fields.next().is_some() {
+            return Err("Extra field");
+        }
+
+        self.schedules
+            .push(Schedule {
+                frame: Frame::new_data(id, data),
+                period: Duration::<u32, 1, TICK_RATE>::millis(period),
+                offset: Duration::<u32, 1, TICK_RATE>::millis(offset),
+                next: Instant::<u32, 1, TICK_RATE>::from_ticks(0),
+            })
+            .map_err(|_| "Too many")
+    }
+
+    /// Checks that the scheduler can be started.
+    ///
+    /// Returns a short error message if no frame was defined.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.schedules.is_empty() {
+            Err("No frame")
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Schedules the first emission of every frame relative to `now`.
+    pub fn start(&mut self, now: Instant<u32, 1, TICK_RATE>) {
+        for schedule in &mut self.schedules {
+            schedule.next = now + schedule.offset;
+        }
+    }
+
+    /// Returns the frame whose emission is the most overdue at `now`, and schedules its next
+    /// emission.
+    ///
+    /// If more than one period was missed, the next emission is scheduled one period after `now`
+    /// rather than sending the missed ones in a burst.
+    ///
+    /// Returns [`None`] if no emission is due yet.
+    pub fn next_frame(&mut self, now: Instant<u32, 1, TICK_RATE>) -> Option<Frame> {
+        let schedule = self
+            .schedules
+            .iter_mut()
+            .filter(|s| s.next <= now)
+            .min_by_key(|s| s.next)?;
+        schedule.next += schedule.period;
+        if schedule.next <= now {
+            schedule.next = now + schedule.period;
+        }
+
+        Some(schedule.frame.clone())
+    }
+
+    /// Returns the time of the next emission.
+    pub fn next_deadline(&self) -> Instant<u32, 1, TICK_RATE> {
+        self.schedules
+            .iter()
+            .map(|s| s.next)
+            .min()
+            .expect("Scheduler should be validated before use.")
+    }
+}
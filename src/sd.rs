@@ -1,17 +1,34 @@
 //! Functions and abstractions for interacting with a SD card.
 
-use core::{cmp::Ordering, str::FromStr};
+use core::{cmp::Ordering, fmt::Write, str::FromStr};
 
-use bxcan::{Frame, StandardId};
+use bxcan::{Data, Frame};
 use embedded_sdmmc::{self as sdmmc, ShortFileName, TimeSource, Timestamp};
 use heapless::{String, Vec};
+use rtic::Mutex;
 use rtic_monotonics::Monotonic;
+use rtt_target::rprintln;
 use stm32f1xx_hal::gpio::{Alternate, Pin};
 
 use crate::{
-    app::{Mono, TICK_RATE},
+    app::{
+        Mono, DBC_FILE_NAME, E2E_FILE_NAME, LOG_INDEX_STRIDE, MAX_BAD_LINES, MAX_DBC_SIGNALS,
+        MAX_REWRITE_RULES, TICK_RATE,
+    },
+    asc::AscLogsIterator,
+    binlog::BinLogsIterator,
+    can::parse_id,
+    e2e::Protections,
+    filter::{FilterMode, IdFilter},
+    gvret::GvretLogsIterator,
+    playlist::Playlist,
+    range::LogRange,
     render::formatted_string,
+    rules::Rule,
+    schedule::Scheduler,
+    signal::{parse_dbc_message, parse_dbc_signal, DbcSignal},
     spi::*,
+    trc::TrcLogsIterator,
 };
 
 /// Length in bytes of CAN logs lines generated by [`frame_to_log()`].
@@ -19,7 +36,7 @@ use crate::{
 /// **WARNING**: if the length is too short, all calls to [`frame_to_log()`] will panic due to a
 /// buffer overflow of the generated log line. Don't forget to count the ending `\n` in the buffer
 /// length.
-const LOG_LINE_LEN: usize = 46;
+const LOG_LINE_LEN: usize = 53;
 /// Size in bytes of the read buffer of [`CanLogsIterator`]s.
 ///
 /// Log files will be read by chunks of [`READ_BUFFER_SIZE`] bytes. To help reduce use of expensive
@@ -37,6 +54,10 @@ const _: () = assert!(
     STORE_BUFFER_SIZE >= READ_BUFFER_SIZE,
     "The store buffer must be at least as large as the read buffer"
 );
+/// Number of index entries written at once while building a log file index.
+///
+/// See [`build_log_index()`] for details.
+const INDEX_BATCH_SIZE: usize = 32;
 
 /// Type alias for the [`embedded_sdmmc::SdCard`].
 pub type SdCard = sdmmc::SdCard<
@@ -57,13 +78,12 @@ pub type File<'a> = sdmmc::File<'a, SdCard, FakeTimeSource, 2, 2, 1>;
 
 /// Decodes a string of up to 8 bytes in hexadecimal into a [`Vec<u8>`].
 ///
-/// `s` can only contain characters `0-9a-zA-Z`. If `s` contains an uneven number of characters,
-/// the last character will be ignored. If `s` contains more than 8 bytes, remaining bytes will be
-/// ignored.
+/// `s` can only contain characters `0-9a-fA-F` and must contain an even number of characters, for
+/// at most 8 bytes.
 ///
 /// Returns an [`Err<()>`] in case of parsing error.
 pub fn decode_hex(s: &str) -> Result<Vec<u8, 8>, ()> {
-    if s.len() % 2 != 0 {
+    if !s.len().is_multiple_of(2) || s.len() > 16 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
         Err(())
     } else {
         (0..s.len())
@@ -73,130 +93,1138 @@ pub fn decode_hex(s: &str) -> Result<Vec<u8, 8>, ()> {
     }
 }
 
-/// An iterator over CAN frames contained in a log file.
+/// Parses the frame field of a log line, in the format of the Linux `can-utils`.
 ///
-/// See [`CanLogsIterator::next()`] for parsing details.
-pub struct CanLogsIterator<'a> {
-    /// The owned [`File`] that will be scanned for CAN frames.
-    log_file: File<'a>,
+/// Supported fields are data frames (`123#0011223344556677`, bytes optionally separated by `.`),
+/// remote frames with an optional length (`123#R`, `123#R4`) and CAN FD frames (`123##1001122`).
+/// Identifiers of 8 digits are extended ones. The DLC suffix of 8-byte data frames
+/// (`123#0011223344556677_C`) is accepted but ignored, the bxCAN peripheral always sending a DLC
+/// of 8.
+///
+/// Returns [`None`] for CAN FD frames, which can't be sent by the bxCAN peripheral, or an
+/// [`Err<()>`] in case of parsing error.
+pub fn parse_frame_field(field: &str) -> Result<Option<Frame>, ()> {
+    let (id, content) = field.split_once('#').ok_or(())?;
+    let id = parse_id(id).ok_or(())?;
+
+    if content.starts_with('#') {
+        return Ok(None);
+    }
+
+    if let Some(length) = content.strip_prefix(['R', 'r']) {
+        let dlc = match length {
+            "" => 0,
+            length => u8::from_str(length)
+                .ok()
+                .filter(|dlc| *dlc <= 8)
+                .ok_or(())?,
+        };
+        return Ok(Some(Frame::new_remote(id, dlc)));
+    }
+
+    let content = match content.split_once('_') {
+        Some((data, dlc))
+            if data.len() >= 16 && u8::from_str_radix(dlc, 16).is_ok_and(|d| d > 8) =>
+        {
+            data
+        }
+        Some(_) => return Err(()),
+        None => content,
+    };
+    let mut hex = String::<16>::new();
+    for c in content.chars().filter(|c| *c != '.') {
+        if !c.is_ascii_hexdigit() {
+            return Err(());
+        }
+        hex.push(c).map_err(|_| ())?;
+    }
+    let data = decode_hex(&hex)?;
+
+    Ok(Some(Frame::new_data(
+        id,
+        Data::new(&data).expect("data contains at most 8 bytes."),
+    )))
+}
+
+/// A source of log file content, read by a [`CanLogsIterator`].
+pub trait LogSource {
+    /// Reads the next chunk of content into `buffer`, returning the number of bytes read.
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, ()>;
+    /// Returns whether the whole content was read.
+    fn is_eof(&mut self) -> bool;
+    /// Moves the reading position to `offset` bytes from the start of the content.
+    fn seek(&mut self, offset: u32) -> Result<(), ()>;
+}
+
+impl LogSource for File<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, ()> {
+        File::read(self, buffer).map_err(|_| ())
+    }
+
+    fn is_eof(&mut self) -> bool {
+        File::is_eof(self)
+    }
+
+    fn seek(&mut self, offset: u32) -> Result<(), ()> {
+        self.seek_from_start(offset).map_err(|_| ())
+    }
+}
+
+/// Lines of a log file that couldn't be read or parsed, skipped by log file iterators.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BadLines {
+    /// Number of lines skipped.
+    pub count: u32,
+    /// Numbers of the first lines skipped, starting at 1.
+    pub first: Vec<u32, MAX_BAD_LINES>,
+}
+
+/// A reader splitting the content of a [`LogSource`] into lines, for log file iterators.
+///
+/// Unlike a [`LineReader`], it keeps track of the offset of each line in the log file, so that
+/// reading can be resumed from a given line (see [`LogLines::seek()`]). Lines that aren't valid
+/// UTF-8 are skipped, and recorded in [`bad_lines()`][LogLines::bad_lines()] along with the lines
+/// rejected by the iterators (see [`reject()`][LogLines::reject()]).
+pub struct LogLines<S: LogSource> {
+    /// The owned [`LogSource`] that will be split into lines.
+    log_file: S,
     /// A buffer containing file reads artefacts.
     ///
-    /// The [`log_file`][CanLogsIterator::log_file] will be read in chunk of size
-    /// [`READ_BUFFER_SIZE`] into [`stored`][CanLogsIterator::stored].
-    /// [`stored`][CanLogsIterator::stored] will then get parsed to extract the contained CAN
-    /// frames.
-    stored: String<STORE_BUFFER_SIZE>,
+    /// The [`log_file`][LogLines::log_file] will be read in chunk of size [`READ_BUFFER_SIZE`]
+    /// into [`stored`][LogLines::stored]. [`stored`][LogLines::stored] will then get split to
+    /// extract the contained lines. Bytes are only decoded once a whole line was read, so that
+    /// UTF-8 characters split between two reads are kept whole.
+    stored: Vec<u8, STORE_BUFFER_SIZE>,
+    /// Offset in bytes of the first line of [`stored`][LogLines::stored] in the log file.
+    offset: u32,
+    /// Number of the last line returned, starting at 1 from the position of the last seek.
+    line_number: u32,
+    /// Lines skipped since the position of the last seek.
+    bad_lines: BadLines,
 }
 
-impl<'a> CanLogsIterator<'a> {
-    /// Creates a new [`CanLogsIterator`] from a given [`File`].
-    pub fn new(log_file: File<'a>) -> Self {
+impl<S: LogSource> LogLines<S> {
+    /// Creates a new [`LogLines`] from a given [`LogSource`].
+    pub fn new(log_file: S) -> Self {
         Self {
             log_file,
-            stored: String::new(),
+            stored: Vec::new(),
+            offset: 0,
+            line_number: 0,
+            bad_lines: BadLines::default(),
         }
     }
+
+    /// Moves to the line starting `offset` bytes from the start of the log file, as returned by
+    /// [`offset()`][LogLines::offset()].
+    ///
+    /// Line numbers are then counted from this line.
+    pub fn seek(&mut self, offset: u32) -> Result<(), ()> {
+        self.log_file.seek(offset)?;
+        self.stored.clear();
+        self.offset = offset;
+        self.line_number = 0;
+        self.bad_lines = BadLines::default();
+        Ok(())
+    }
+
+    /// Returns the offset in bytes of the next line to be read in the log file.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the lines skipped so far, see [`reject()`][LogLines::reject()].
+    pub fn bad_lines(&self) -> &BadLines {
+        &self.bad_lines
+    }
+
+    /// Records the last line returned by [`next_line()`][LogLines::next_line()] as a bad line,
+    /// for lines that couldn't be parsed.
+    pub fn reject(&mut self) {
+        self.bad_lines.count += 1;
+        let _ = self.bad_lines.first.push(self.line_number); // Only the first ones are kept
+    }
+
+    /// Returns a mutable reference to the [`LogSource`] being read.
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.log_file
+    }
+
+    /// Reads the next line of the [`log_file`][LogLines::log_file], without its line feed.
+    ///
+    /// This is done by first loading a chunk of the [`log_file`][LogLines::log_file] into the
+    /// [`stored`][LogLines::stored] buffer. We then try to extract a line from the buffer, if no
+    /// line feed (`\n`) is found, the read operation is repeated. The last line doesn't need to
+    /// be terminated.
+    ///
+    /// Lines not fitting in [`STORE_BUFFER_SIZE`] bytes with their line feed are skipped up to it
+    /// and recorded as bad lines, like lines that aren't valid UTF-8.
+    ///
+    /// If any error is encountered while reading the file, [`None`] is returned and iteration ends.
+    pub fn next_line(&mut self) -> Option<String<STORE_BUFFER_SIZE>> {
+        // Whether the start of a line too long to fit was dropped, its end being looked for
+        let mut draining = false;
+
+        loop {
+            let line = if let Some(i) = self.stored.iter().position(|b| *b == b'\n') {
+                let line = Vec::from_slice(&self.stored[..i]).expect("Line fits in stored.");
+                self.consume(i + 1);
+                line
+            } else if self.stored.is_full() {
+                draining = true;
+                self.consume(STORE_BUFFER_SIZE);
+                continue;
+            } else if self.log_file.is_eof() {
+                if self.stored.is_empty() && !draining {
+                    return None;
+                }
+                let line = self.stored.clone();
+                self.consume(self.stored.len());
+                line
+            } else {
+                let mut buffer = [0u8; READ_BUFFER_SIZE];
+                let free = (STORE_BUFFER_SIZE - self.stored.len()).min(READ_BUFFER_SIZE);
+                let read_count = self.log_file.read(&mut buffer[..free]).ok()?; // ? Read error
+                self.stored
+                    .extend_from_slice(&buffer[..read_count])
+                    .expect("Buffer fits in stored (checked above).");
+                continue;
+            };
+
+            self.line_number += 1;
+            match String::from_utf8(line) {
+                Ok(line) if !draining => return Some(line),
+                _ => {
+                    self.reject();
+                    draining = false;
+                }
+            }
+        }
+    }
+
+    /// Removes the first `count` bytes of [`stored`][LogLines::stored], moving the
+    /// [`offset`][LogLines::offset] past them.
+    fn consume(&mut self, count: usize) {
+        let len = self.stored.len();
+        self.stored.copy_within(count..len, 0);
+        self.stored.truncate(len - count);
+        self.offset += count as u32;
+    }
 }
 
-impl Iterator for CanLogsIterator<'_> {
-    type Item = Frame;
+/// An iterator over CAN frames contained in a log file.
+///
+/// The log file is usually a [`File`], but can be any [`LogSource`] such as [`SharedLogFiles`].
+///
+/// See [`CanLogsIterator::next()`] for parsing details.
+pub struct CanLogsIterator<S: LogSource> {
+    /// The lines of the log file that will be scanned for CAN frames.
+    lines: LogLines<S>,
+    /// Time of the last frame read, in microseconds, or `0` if no frame had a timestamp.
+    timestamp: u64,
+}
+
+impl<S: LogSource> CanLogsIterator<S> {
+    /// Creates a new [`CanLogsIterator`] from a given [`LogSource`].
+    pub fn new(log_file: S) -> Self {
+        Self {
+            lines: LogLines::new(log_file),
+            timestamp: 0,
+        }
+    }
+
+    /// Moves to the line starting `offset` bytes from the start of the log file, as returned by
+    /// [`offset()`][CanLogsIterator::offset()].
+    pub fn seek(&mut self, offset: u32) -> Result<(), ()> {
+        self.lines.seek(offset)
+    }
 
-    /// Reads the next [`Frame`] from the [`log_file`][CanLogsIterator::log_file].
+    /// Returns the offset in bytes of the next line to be read in the log file.
+    pub fn offset(&self) -> u32 {
+        self.lines.offset()
+    }
+
+    /// Returns the time of the last frame read, in seconds.
     ///
-    /// This is done by first loading a chunk of the [`log_file`][CanLogsIterator::log_file] into
-    /// the [`stored`][CanLogsIterator::stored] buffer. We then try to extract a line from the
-    /// buffer, if no line feed (`\n`) is found, the read operation is repeated if enough space in
-    /// the buffer is left.
+    /// Frames without timestamp keep the time of the previous frame, `0` for the first ones.
+    pub fn timestamp(&self) -> u32 {
+        (self.timestamp / 1_000_000) as u32
+    }
+
+    /// Returns the time of the last frame read, in microseconds.
+    pub fn timestamp_micros(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns the lines skipped so far because they couldn't be parsed.
+    pub fn bad_lines(&self) -> &BadLines {
+        self.lines.bad_lines()
+    }
+
+    /// Returns a mutable reference to the [`LogSource`] being read.
+    pub fn source_mut(&mut self) -> &mut S {
+        self.lines.source_mut()
+    }
+}
+
+impl<S: LogSource> Iterator for CanLogsIterator<S> {
+    type Item = Frame;
+
+    /// Reads the next [`Frame`] from the log file.
     ///
-    /// The extracted line is then parsed with a logic equivalent to the following regex :
-    /// `/.* ([0-9A-F]{3})#([0-9A-F]{16})\n/i`. This is done by grabbing anything after the last
-    /// space (` `) in the line and extracting the [`Frame`] id and data.
+    /// Lines are read by [`LogLines::next_line()`], then parsed following the log format of the
+    /// Linux `can-utils` (`candump -l`) : `(1700000000.123456) can0 123#0011223344556677`. The
+    /// timestamp and interface are optional and the interface name is ignored, a trailing
+    /// direction flag (`T` or `R`, from `candump -x`) too. The frame field is parsed by
+    /// [`parse_frame_field()`], CAN FD frames being skipped with a warning.
     ///
     /// If any error is encountered while reading the file, [`None`] is returned and iteration ends.
-    /// If any error occurs during parsing, [`None`] is returned and iteration ends (this behavior
-    /// should be changed).
+    /// Lines that can't be parsed are skipped, and recorded in
+    /// [`bad_lines()`][CanLogsIterator::bad_lines()]. Empty lines are skipped too.
     ///
     /// # To-Do
-    /// - [ ] Replace errors marked with `!?` in source with a line skip instead of an iteration
+    /// - [x] Replace errors marked with `!?` in source with a line skip instead of an iteration
     ///      end. *(Errors marked `?` are irrecoverable and already well handled.)*
-    /// - [ ] Improve line parsing to allow lines longer than [`STORE_BUFFER_SIZE`].
-    /// - [ ] Improve line parsing to allow frames with payloads shorter than 8 bytes.
-    /// - [ ] Make sure the `stored` buffer is consumed even after EOF is reached.
+    /// - [x] Improve line parsing to allow frames with payloads shorter than 8 bytes.
+    #[allow(clippy::doc_overindented_list_items)]
     fn next(&mut self) -> Option<Self::Item> {
-        while !self.log_file.is_eof() {
-            if STORE_BUFFER_SIZE - self.stored.len() >= READ_BUFFER_SIZE {
-                let mut buffer = [0u8; READ_BUFFER_SIZE];
-                let read_count = self.log_file.read(&mut buffer).ok()?; // ? Read error
-                self.stored
-                    .push_str(core::str::from_utf8(&buffer[..read_count]).ok()?) // ? utf-8 char got split
-                    .expect("Buffer fits in stored (checked at if).");
+        while let Some(log_line) = self.lines.next_line() {
+            if log_line.starts_with('#') {
+                continue; // skip comment lines
             }
 
-            let new_line_i = self.stored.find("\n");
+            let mut fields = log_line.split_whitespace();
+            let Some(mut frame_field) = fields.next() else {
+                continue; // skip empty lines
+            };
+            if let Some(timestamp) = frame_field.strip_prefix('(') {
+                if let Some(micros) = parse_micros(timestamp.trim_end_matches(')'), 1_000_000) {
+                    self.timestamp = micros;
+                }
+                // Skip the interface
+                let (Some(_), Some(field)) = (fields.next(), fields.next()) else {
+                    self.lines.reject(); // no frame
+                    continue;
+                };
+                frame_field = field;
+            }
 
-            let new_line_i = if let Some(i) = new_line_i {
-                i
-            } else if STORE_BUFFER_SIZE - self.stored.len() >= READ_BUFFER_SIZE {
-                continue;
-            } else {
-                break;
+            match parse_frame_field(frame_field) {
+                Ok(Some(frame)) => return Some(frame),
+                Ok(None) => {
+                    rprintln!("WARNING - CAN FD frame skipped: {}", log_line);
+                }
+                Err(()) => self.lines.reject(), // invalid frame
+            }
+        }
+        None
+    }
+}
+
+/// An iterator over CAN frames contained in a log file of any supported format.
+///
+/// The format is chosen by the extension of the log file, see [`LogsIterator::new()`].
+pub enum LogsIterator<S: LogSource> {
+    /// Linux `can-utils` log file, see [`CanLogsIterator`].
+    Log(CanLogsIterator<S>),
+    /// Vector ASC log file, see [`AscLogsIterator`].
+    Asc(AscLogsIterator<S>),
+    /// CANary binary log file, see [`BinLogsIterator`].
+    Bin(BinLogsIterator<S>),
+    /// GVRET CSV log file, see [`GvretLogsIterator`].
+    Gvret(GvretLogsIterator<S>),
+    /// PEAK TRC log file, see [`TrcLogsIterator`].
+    Trc(TrcLogsIterator<S>),
+}
+
+impl<S: LogSource> LogsIterator<S> {
+    /// Creates a new [`LogsIterator`] from a given [`LogSource`], named `file_name`.
+    ///
+    /// Files with an `.ASC` extension are read by an [`AscLogsIterator`], files with a `.CNB`
+    /// extension by a [`BinLogsIterator`], files with a `.CSV` extension by a
+    /// [`GvretLogsIterator`], files with a `.TRC` extension by a [`TrcLogsIterator`], other files
+    /// by a [`CanLogsIterator`].
+    pub fn new(log_file: S, file_name: &ShortFileName) -> Self {
+        match file_name.extension() {
+            b"ASC" => Self::Asc(AscLogsIterator::new(log_file)),
+            b"CNB" => Self::Bin(BinLogsIterator::new(log_file)),
+            b"CSV" => Self::Gvret(GvretLogsIterator::new(log_file)),
+            b"TRC" => Self::Trc(TrcLogsIterator::new(log_file)),
+            _ => Self::Log(CanLogsIterator::new(log_file)),
+        }
+    }
+
+    /// Moves to the line starting `offset` bytes from the start of the log file, as returned by
+    /// [`offset()`][LogsIterator::offset()].
+    pub fn seek(&mut self, offset: u32) -> Result<(), ()> {
+        match self {
+            Self::Log(logs) => logs.seek(offset),
+            Self::Asc(logs) => logs.seek(offset),
+            Self::Bin(logs) => logs.seek(offset),
+            Self::Gvret(logs) => logs.seek(offset),
+            Self::Trc(logs) => logs.seek(offset),
+        }
+    }
+
+    /// Returns the offset in bytes of the next line to be read in the log file.
+    pub fn offset(&self) -> u32 {
+        match self {
+            Self::Log(logs) => logs.offset(),
+            Self::Asc(logs) => logs.offset(),
+            Self::Bin(logs) => logs.offset(),
+            Self::Gvret(logs) => logs.offset(),
+            Self::Trc(logs) => logs.offset(),
+        }
+    }
+
+    /// Returns the time of the last frame read, in seconds.
+    pub fn timestamp(&self) -> u32 {
+        match self {
+            Self::Log(logs) => logs.timestamp(),
+            Self::Asc(logs) => logs.timestamp(),
+            Self::Bin(logs) => logs.timestamp(),
+            Self::Gvret(logs) => logs.timestamp(),
+            Self::Trc(logs) => logs.timestamp(),
+        }
+    }
+
+    /// Returns the time of the last frame read, in microseconds.
+    pub fn timestamp_micros(&self) -> u64 {
+        match self {
+            Self::Log(logs) => logs.timestamp_micros(),
+            Self::Asc(logs) => logs.timestamp_micros(),
+            Self::Bin(logs) => logs.timestamp_micros(),
+            Self::Gvret(logs) => logs.timestamp_micros(),
+            Self::Trc(logs) => logs.timestamp_micros(),
+        }
+    }
+
+    /// Returns the lines (or records of binary log files) skipped so far because they couldn't be
+    /// parsed.
+    pub fn bad_lines(&self) -> &BadLines {
+        match self {
+            Self::Log(logs) => logs.bad_lines(),
+            Self::Asc(logs) => logs.bad_lines(),
+            Self::Bin(logs) => logs.bad_records(),
+            Self::Gvret(logs) => logs.bad_lines(),
+            Self::Trc(logs) => logs.bad_lines(),
+        }
+    }
+
+    /// Returns a mutable reference to the [`LogSource`] being read.
+    pub fn source_mut(&mut self) -> &mut S {
+        match self {
+            Self::Log(logs) => logs.source_mut(),
+            Self::Asc(logs) => logs.source_mut(),
+            Self::Bin(logs) => logs.source_mut(),
+            Self::Gvret(logs) => logs.source_mut(),
+            Self::Trc(logs) => logs.source_mut(),
+        }
+    }
+}
+
+impl<S: LogSource> Iterator for LogsIterator<S> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Log(logs) => logs.next(),
+            Self::Asc(logs) => logs.next(),
+            Self::Bin(logs) => logs.next(),
+            Self::Gvret(logs) => logs.next(),
+            Self::Trc(logs) => logs.next(),
+        }
+    }
+}
+
+/// A log file read through a shared [`VolumeManager`], along with an optional log file written,
+/// such as the transcript of its replay.
+///
+/// [`File`]s borrow the [`VolumeManager`] for their whole lifetime, which would keep it locked
+/// during a whole emission. Both files are kept open through raw handles instead, the volume
+/// manager being only locked for each access so that emissions can wait between frames. They are
+/// closed when dropped.
+pub struct SharedLogFiles<'a, V: Mutex<T = VolumeManager>> {
+    /// Shared volume manager both files are accessed through.
+    volume_manager: &'a mut V,
+    /// Log file read, opened read-only.
+    input: sdmmc::RawFile,
+    /// Log file written, created or truncated when opened.
+    output: Option<sdmmc::RawFile>,
+}
+
+impl<'a, V: Mutex<T = VolumeManager>> SharedLogFiles<'a, V> {
+    /// Opens the log file `input_name` of `dir`, and creates the log file `output_name` in it if
+    /// any.
+    pub fn open(
+        volume_manager: &'a mut V,
+        dir: sdmmc::RawDirectory,
+        input_name: &ShortFileName,
+        output_name: Option<&str>,
+    ) -> Result<Self, sdmmc::Error<sdmmc::SdCardError>> {
+        let (input, output) = volume_manager.lock(|vm| {
+            let input = vm.open_file_in_dir(dir, input_name, sdmmc::Mode::ReadOnly)?;
+            let Some(output_name) = output_name else {
+                return Ok((input, None));
             };
-            let stored_clone = self.stored.clone();
+            match vm.open_file_in_dir(dir, output_name, sdmmc::Mode::ReadWriteCreateOrTruncate) {
+                Ok(output) => Ok((input, Some(output))),
+                Err(error) => {
+                    let _ = vm.close_file(input);
+                    Err(error)
+                }
+            }
+        })?;
+
+        Ok(Self {
+            volume_manager,
+            input,
+            output,
+        })
+    }
 
-            let log_line = &stored_clone[..new_line_i];
-            self.stored = String::from_str(&stored_clone[new_line_i + 1..])
-                .expect("Stored slice fits in stored.");
+    /// Appends `buffer` to the output file, if any.
+    pub fn write(&mut self, buffer: &[u8]) -> Result<(), sdmmc::Error<sdmmc::SdCardError>> {
+        match self.output {
+            Some(output) => self.volume_manager.lock(|vm| vm.write(output, buffer)),
+            None => Ok(()),
+        }
+    }
+}
 
-            if log_line.starts_with('#') {
-                continue; // skip comment lines
+impl<V: Mutex<T = VolumeManager>> LogSource for SharedLogFiles<'_, V> {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, ()> {
+        self.volume_manager
+            .lock(|vm| vm.read(self.input, buffer))
+            .map_err(|_| ())
+    }
+
+    fn is_eof(&mut self) -> bool {
+        self.volume_manager
+            .lock(|vm| vm.file_eof(self.input))
+            .unwrap_or(true)
+    }
+
+    fn seek(&mut self, offset: u32) -> Result<(), ()> {
+        self.volume_manager
+            .lock(|vm| vm.file_seek_from_start(self.input, offset))
+            .map_err(|_| ())
+    }
+}
+
+impl<V: Mutex<T = VolumeManager>> Drop for SharedLogFiles<'_, V> {
+    fn drop(&mut self) {
+        self.volume_manager.lock(|vm| {
+            let _ = vm.close_file(self.input);
+            if let Some(output) = self.output {
+                let _ = vm.close_file(output);
+            }
+        });
+    }
+}
+
+/// Locks `volume_manager` and calls `f` with the directory `dir`, kept open through a raw handle.
+pub fn with_dir<R>(
+    volume_manager: &mut impl Mutex<T = VolumeManager>,
+    dir: sdmmc::RawDirectory,
+    f: impl FnOnce(&mut Directory) -> R,
+) -> R {
+    volume_manager.lock(|vm| {
+        let mut dir = dir.to_directory(vm);
+        let result = f(&mut dir);
+        dir.to_raw_directory(); // Kept open
+        result
+    })
+}
+
+/// Error returned by [`LineReader::next_line()`] for a line that couldn't be read.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineError {
+    /// The line didn't fit in the buffer of the [`LineReader`]. The line was skipped.
+    TooLong,
+    /// The line isn't valid UTF-8. The line was skipped.
+    NotUtf8,
+}
+
+/// A reader splitting the content of a [`File`] into lines.
+///
+/// Lines are read into a buffer of `N` bytes, lines longer than that are skipped and reported
+/// as [`LineError::TooLong`]. Both `\n` and `\r\n` line endings are supported, and the last
+/// line doesn't need to be terminated.
+pub struct LineReader<'a, const N: usize> {
+    /// The owned [`File`] that will be split into lines.
+    file: File<'a>,
+    /// Buffer containing file reads artefacts.
+    buffer: [u8; N],
+    /// Start of the unread content in [`buffer`][LineReader::buffer].
+    start: usize,
+    /// End of the unread content in [`buffer`][LineReader::buffer].
+    end: usize,
+    /// Number of the last line returned, starting at 1.
+    line_number: u32,
+}
+
+impl<'a, const N: usize> LineReader<'a, N> {
+    /// Creates a new [`LineReader`] from a given [`File`].
+    pub fn new(file: File<'a>) -> Self {
+        Self {
+            file,
+            buffer: [0; N],
+            start: 0,
+            end: 0,
+            line_number: 0,
+        }
+    }
+
+    /// Returns the number of the last line returned by [`next_line()`][LineReader::next_line()],
+    /// starting at 1.
+    pub fn line_number(&self) -> u32 {
+        self.line_number
+    }
+
+    /// Reads the next line of the file, without its line ending.
+    ///
+    /// Returns [`None`] once the whole file was read or if a read error occurred.
+    pub fn next_line(&mut self) -> Option<Result<&str, LineError>> {
+        let mut too_long = false;
+
+        loop {
+            // Look for a line feed in the unread content
+            if let Some(i) = self.buffer[self.start..self.end]
+                .iter()
+                .position(|b| *b == b'\n')
+            {
+                let line = (self.start, self.start + i);
+                self.start += i + 1;
+                return Some(self.take_line(line, too_long));
+            }
+
+            // Move the unread content to the start of the buffer
+            self.buffer.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+
+            if self.end == N {
+                // The line can't fit, drop what was read and look for its end
+                too_long = true;
+                self.end = 0;
             }
 
-            let mut frame_bytes = log_line.split(" ").last()?.split("#"); // ? Infaillible
-            let frame_id = u16::from_str_radix(frame_bytes.next()?, 16).ok()?; // !? frame_bytes doesn't have >= 1 elements || !? invalid hexadecimal
-            let frame_data = decode_hex(frame_bytes.next()?).ok()?; // !? frame_bytes doesn't have >= 2 elements || !? invalid hexadecimal
+            if self.file.is_eof() {
+                if self.end == 0 && !too_long {
+                    return None;
+                }
+                let line = (0, self.end);
+                self.start = self.end;
+                return Some(self.take_line(line, too_long));
+            }
 
-            return Some(Frame::new_data(
-                StandardId::new(frame_id)?,         // !? frame_id doesn't fit in 11 bits
-                frame_data.into_array::<8>().ok()?, // !? frame_data didn't contain 8 bytes
-            ));
+            self.end += self.file.read(&mut self.buffer[self.end..]).ok()?;
         }
-        None
+    }
+
+    /// Returns the line contained in [`buffer`][LineReader::buffer] between the given bounds.
+    fn take_line(
+        &mut self,
+        (start, end): (usize, usize),
+        too_long: bool,
+    ) -> Result<&str, LineError> {
+        self.line_number += 1;
+        if too_long {
+            return Err(LineError::TooLong);
+        }
+
+        let line = self.buffer[start..end]
+            .strip_suffix(b"\r")
+            .unwrap_or(&self.buffer[start..end]);
+        core::str::from_utf8(line).map_err(|_| LineError::NotUtf8)
     }
 }
 
-/// Formats a given [`Frame`] as a log line.
+/// Reads the configuration file `file_name` of `dir` line by line, with lines of at most `N`
+/// bytes, passing each line and its number to `parse`.
 ///
-/// If the frame doesn't have a full 8 bytes payload, padding in the form of `FF` will be used.
+/// If there is no such file, `missing` is returned as error, or nothing is parsed if it is
+/// `None`. Returns a short error message and the number of the faulty line if a line can't be
+/// read or parsed.
+fn load_config_lines<const N: usize>(
+    dir: &mut Directory,
+    file_name: &ShortFileName,
+    missing: Option<&'static str>,
+    mut parse: impl FnMut(&str, u32) -> Result<(), &'static str>,
+) -> Result<(), (&'static str, u32)> {
+    let file = match dir.open_file_in_dir(file_name, sdmmc::Mode::ReadOnly) {
+        Ok(file) => file,
+        Err(sdmmc::Error::NotFound) => return missing.map_or(Ok(()), |e| Err((e, 0))),
+        Err(_) => return Err(("Can't open", 0)),
+    };
+
+    let mut lines = LineReader::<N>::new(file);
+    loop {
+        let line_number = lines.line_number() + 1;
+        let Some(line) = lines.next_line() else {
+            break;
+        };
+        let result = match line {
+            Ok(line) => parse(line, line_number),
+            Err(LineError::TooLong) => Err("Too long"),
+            Err(LineError::NotUtf8) => Err("Not UTF-8"),
+        };
+        result.map_err(|e| (e, line_number))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the name of the file with the same name as `file_name` and the extension `extension`.
+fn sibling_file_name(
+    file_name: &ShortFileName,
+    extension: &str,
+) -> Result<ShortFileName, (&'static str, u32)> {
+    let name: String<12> = formatted_string(format_args!(
+        "{}.{}",
+        core::str::from_utf8(file_name.base_name()).map_err(|_| ("Bad name", 0))?,
+        extension
+    ))
+    .expect("Formatted args should fit.");
+    ShortFileName::create_from_str(&name).map_err(|_| ("Bad name", 0))
+}
+
+/// Loads the signals defined in the [`DBC_FILE_NAME`] file at the root of the SD card.
 ///
-/// Format is as follows :
+/// Only the message and signal definitions are read, the rest of the file is ignored. A missing
+/// file or a read error results in an empty (or truncated) list of signals.
+pub fn load_dbc_signals(volume_manager: &mut VolumeManager) -> Vec<DbcSignal, MAX_DBC_SIGNALS> {
+    let mut signals = Vec::new();
+
+    let Ok(mut sd_volume) = volume_manager.open_volume(sdmmc::VolumeIdx(0)) else {
+        return signals;
+    };
+    let Ok(mut root_dir) = sd_volume.open_root_dir() else {
+        return signals;
+    };
+    let Ok(file) = root_dir.open_file_in_dir(DBC_FILE_NAME, sdmmc::Mode::ReadOnly) else {
+        return signals;
+    };
+
+    let mut lines = LineReader::<128>::new(file);
+    let mut message_id = None;
+    while let Some(line) = lines.next_line() {
+        let Ok(line) = line else {
+            continue;
+        };
+
+        if let Some(id) = parse_dbc_message(line) {
+            message_id = Some(id);
+        } else if let (Some(id), Some((name, signal))) = (message_id, parse_dbc_signal(line)) {
+            if signals.push(DbcSignal { id, name, signal }).is_err() {
+                break;
+            }
+        }
+    }
+
+    signals
+}
+
+/// Loads the counters and checksums defined in the [`E2E_FILE_NAME`] file of `root_dir`.
+///
+/// If there is no such file, frames are left unchanged. Returns a short error message and the
+/// number of the faulty line if a protection is invalid.
+pub fn load_protections(root_dir: &mut Directory) -> Result<Protections, (&'static str, u32)> {
+    let mut protections = Protections::new();
+    let file_name =
+        ShortFileName::create_from_str(E2E_FILE_NAME).expect("E2E file name should be valid.");
+    load_config_lines::<96>(root_dir, &file_name, None, |line, _| {
+        protections.parse_line(line)
+    })?;
+
+    Ok(protections)
+}
+
+/// Loads the rewrite rules of the log file `file_name` from `dir` into `rules`, replacing its
+/// previous content.
+///
+/// Rules are read from the file with the same name and a `.RUL` extension, if there is none
+/// `rules` is left empty.
+///
+/// Returns a short error message and the number of the faulty line if a rule is invalid, or if
+/// there are more than [`MAX_REWRITE_RULES`] rules.
+pub fn load_rules(
+    dir: &mut Directory,
+    file_name: &ShortFileName,
+    rules: &mut Vec<Rule, MAX_REWRITE_RULES>,
+) -> Result<(), (&'static str, u32)> {
+    rules.clear();
+    load_config_lines::<96>(
+        dir,
+        &sibling_file_name(file_name, "RUL")?,
+        None,
+        |line, _| {
+            if let Some(rule) = Rule::parse(line)? {
+                rules.push(rule).map_err(|_| "Too many")?;
+            }
+            Ok(())
+        },
+    )
+}
+
+/// Loads the identifier filter of the log file `file_name` from `dir` into `filter`.
+///
+/// The filter is a copy of `settings`, the one edited on the settings screen, unless its mode is
+/// [`FilterMode::File`]. It is then read from the file with the same name and a `.FLT` extension
+/// (see [`IdFilter`]).
+///
+/// Returns a short error message and the number of the faulty line if the filter file is missing
+/// or invalid.
+pub fn load_filter(
+    dir: &mut Directory,
+    file_name: &ShortFileName,
+    settings: &IdFilter,
+    filter: &mut IdFilter,
+) -> Result<(), (&'static str, u32)> {
+    if settings.mode != FilterMode::File {
+        filter.clone_from(settings);
+        return Ok(());
+    }
+
+    *filter = IdFilter::new();
+    filter.mode = FilterMode::Keep;
+    load_config_lines::<96>(
+        dir,
+        &sibling_file_name(file_name, "FLT")?,
+        Some("No filter"),
+        |line, _| filter.parse_line(line),
+    )
+}
+
+/// Position in a log file where the replay of a [`LogRange`] starts.
+#[derive(Debug)]
+pub struct LogPosition {
+    /// Offset in bytes of the line of the frame, see [`CanLogsIterator::seek()`].
+    pub offset: u32,
+    /// Index of the frame in the log file.
+    pub index: u32,
+    /// Time of the first frame of the log file in seconds, range times being relative to it.
+    pub origin: u32,
+}
+
+/// Finds where the replay of `range` starts in the log file `file_name` of `dir`.
+///
+/// The whole log file is replayed from its start without index. Otherwise, the position is looked
+/// for in the index of the log file, a file with the same name and a `.IDX` extension, which is
+/// built first if missing or outdated (see [`build_log_index()`]). The returned position is at
+/// most [`LOG_INDEX_STRIDE`] frames before the start of the range, so that only these frames have
+/// to be parsed to reach it.
+///
+/// Returns a short error message if the index can't be built.
+pub fn locate_log_range(
+    dir: &mut Directory,
+    file_name: &ShortFileName,
+    range: &LogRange,
+) -> Result<LogPosition, (&'static str, u32)> {
+    if range.is_all() {
+        return Ok(LogPosition {
+            offset: 0,
+            index: 0,
+            origin: 0,
+        });
+    }
+
+    let index_name: String<12> = formatted_string(format_args!(
+        "{}.IDX",
+        core::str::from_utf8(file_name.base_name()).map_err(|_| ("Bad name", 0))?
+    ))
+    .expect("Formatted args should fit.");
+    let length = dir
+        .open_file_in_dir(file_name, sdmmc::Mode::ReadOnly)
+        .map_err(|_| ("Can't open", 0))?
+        .length();
+
+    if let Some(position) = search_log_index(dir, &index_name, length, range) {
+        return Ok(position);
+    }
+    build_log_index(dir, file_name, &index_name, length)?;
+    search_log_index(dir, &index_name, length, range).ok_or(("Bad index", 0))
+}
+
+/// Looks for the start of `range` in the index `index_name` of a log file of `length` bytes.
+///
+/// Returns [`None`] if there is no index or if it was built for another version of the log file.
+fn search_log_index(
+    dir: &mut Directory,
+    index_name: &str,
+    length: u32,
+    range: &LogRange,
+) -> Option<LogPosition> {
+    let mut file = dir
+        .open_file_in_dir(index_name, sdmmc::Mode::ReadOnly)
+        .ok()?;
+    let (indexed_length, origin) = read_index_record(&mut file)?;
+    if indexed_length != length {
+        return None;
+    }
+
+    let mut position = LogPosition {
+        offset: 0,
+        index: 0,
+        origin,
+    };
+    let mut index = 0;
+    while let Some((offset, time)) = read_index_record(&mut file) {
+        if !range.can_start_at(index, time.saturating_sub(origin)) {
+            break;
+        }
+        position.offset = offset;
+        position.index = index;
+        index += LOG_INDEX_STRIDE;
+    }
+
+    Some(position)
+}
+
+/// Builds the index `index_name` of the log file `file_name` of `length` bytes.
+///
+/// The index is a binary file of little-endian `u32` pairs. The first pair holds the length of the
+/// log file and the time of its first frame, the next ones hold the offset and time of every
+/// [`LOG_INDEX_STRIDE`]th frame. Only one file can be open at a time, so index entries are
+/// written by batches while the log file is closed.
+///
+/// Returns a short error message if the index can't be written.
+fn build_log_index(
+    dir: &mut Directory,
+    file_name: &ShortFileName,
+    index_name: &str,
+    length: u32,
+) -> Result<(), (&'static str, u32)> {
+    let mut resume_offset = 0;
+    let mut count = 0u32;
+    let mut origin = None;
+    let mut created = false;
+
+    loop {
+        let mut batch: Vec<(u32, u32), INDEX_BATCH_SIZE> = Vec::new();
+        {
+            let mut logs = LogsIterator::new(
+                dir.open_file_in_dir(file_name, sdmmc::Mode::ReadOnly)
+                    .map_err(|_| ("Can't open", 0))?,
+                file_name,
+            );
+            logs.seek(resume_offset).map_err(|_| ("Can't read", 0))?;
+            while !batch.is_full() {
+                let offset = logs.offset();
+                if logs.next().is_none() {
+                    break;
+                }
+                origin.get_or_insert(logs.timestamp());
+                if count.is_multiple_of(LOG_INDEX_STRIDE) {
+                    let _ = batch.push((offset, logs.timestamp()));
+                }
+                count += 1;
+            }
+            resume_offset = logs.offset();
+        }
+
+        let mut index_file = dir
+            .open_file_in_dir(
+                index_name,
+                if created {
+                    sdmmc::Mode::ReadWriteAppend
+                } else {
+                    sdmmc::Mode::ReadWriteCreateOrTruncate
+                },
+            )
+            .map_err(|_| ("Can't write", 0))?;
+        if !created {
+            write_index_record(&mut index_file, (length, origin.unwrap_or(0)))?;
+            created = true;
+        }
+        for record in &batch {
+            write_index_record(&mut index_file, *record)?;
+        }
+
+        if !batch.is_full() {
+            return Ok(());
+        }
+    }
+}
+
+/// Reads a pair of little-endian `u32` from an index file.
+fn read_index_record(file: &mut File) -> Option<(u32, u32)> {
+    let mut record = [0; 8];
+    let mut read_count = 0;
+    while read_count < record.len() {
+        match file.read(&mut record[read_count..]) {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => read_count += n,
+        }
+    }
+
+    Some((
+        u32::from_le_bytes(record[..4].try_into().expect("Slice is 4 bytes long.")),
+        u32::from_le_bytes(record[4..].try_into().expect("Slice is 4 bytes long.")),
+    ))
+}
+
+/// Writes a pair of little-endian `u32` to an index file.
+fn write_index_record(file: &mut File, (a, b): (u32, u32)) -> Result<(), (&'static str, u32)> {
+    let mut record = [0; 8];
+    record[..4].copy_from_slice(&a.to_le_bytes());
+    record[4..].copy_from_slice(&b.to_le_bytes());
+    file.write(&record).map_err(|_| ("Can't write", 0))
+}
+
+/// Loads the schedule `file_name` from `dir`.
+///
+/// Returns a short error message and the number of the faulty line if the schedule is invalid.
+pub fn load_schedule(
+    dir: &mut Directory,
+    file_name: &ShortFileName,
+) -> Result<Scheduler, (&'static str, u32)> {
+    let mut scheduler = Scheduler::new();
+
+    load_config_lines::<96>(dir, file_name, Some("Can't open"), |line, _| {
+        scheduler.parse_line(line)
+    })?;
+    scheduler.validate().map_err(|e| (e, 0))?;
+
+    Ok(scheduler)
+}
+
+/// Loads the playlist `file_name` from `dir`.
+///
+/// Every log file listed must exist in `dir`. Returns a short error message and the number of the
+/// faulty line if the playlist is invalid.
+pub fn load_playlist(
+    dir: &mut Directory,
+    file_name: &ShortFileName,
+) -> Result<Playlist, (&'static str, u32)> {
+    let mut playlist = Playlist::new();
+
+    load_config_lines::<96>(dir, file_name, Some("Can't open"), |line, line_number| {
+        playlist.parse_line(line, line_number)
+    })?;
+    playlist.validate().map_err(|e| (e, 0))?;
+
+    // Files are checked once the playlist is closed, only one file can be open at a time
+    for entry in playlist.entries() {
+        if dir.find_directory_entry(&entry.file_name).is_err() {
+            return Err(("Missing file", entry.line_number));
+        }
+    }
+
+    Ok(playlist)
+}
+
+/// Format of the log files written by captures.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    /// Linux `can-utils` log format, see [`frame_to_log()`].
+    Log,
+    /// CANary binary format, see [`frame_to_bin()`][crate::binlog::frame_to_bin()].
+    Bin,
+}
+
+impl LogFormat {
+    /// Increments `self` to next [`LogFormat`], wrapping around.
+    pub fn increment(&mut self) {
+        *self = match self {
+            Self::Log => Self::Bin,
+            Self::Bin => Self::Log,
+        }
+    }
+
+    /// Decrements `self` to previous [`LogFormat`], wrapping around.
+    pub fn decrement(&mut self) {
+        *self = match self {
+            Self::Bin => Self::Log,
+            Self::Log => Self::Bin,
+        }
+    }
+
+    /// Returns the extension of the files written in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Log => "log",
+            Self::Bin => "cnb",
+        }
+    }
+}
+
+/// Parses a decimal time (`12.345678`) into microseconds, `unit_micros` being the number of
+/// microseconds of its unit (`1_000_000` for seconds).
+///
+/// Digits past the microsecond are ignored. Returns [`None`] if `time` isn't a decimal number.
+pub fn parse_micros(time: &str, unit_micros: u64) -> Option<u64> {
+    let (whole, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut micros = u64::from_str(whole).ok()?.checked_mul(unit_micros)?;
+    let mut scale = unit_micros;
+    for digit in fraction.bytes() {
+        if !digit.is_ascii_digit() {
+            return None;
+        }
+        scale /= 10;
+        micros += (digit - b'0') as u64 * scale;
+    }
+    Some(micros)
+}
+
+/// Formats a given [`Frame`] as a log line, given its time of capture in `ticks`.
+///
+/// Format is the one of `candump -l` from the Linux `can-utils`, so that logs can be replayed by
+/// `canplayer` :
 /// ```logs
-/// (0000375767.000000) can0 001#0123456789ABCDEF
-///  ^^^^^^^^^┤         ^^^┤ ^^┤ ^^^^^^^^^^^^^^^┴─ 8-byte hexadecimal frame payload
-///           │            │   └─ 11-bit hexadecimal identifier
-///           │            └─ Can Interface - always can0
-///           └─ Time of capture (in ticks since boot)
+/// (0000000375.767000) can0 001#0123456789ABCDEF
+///  ^^^^^^^^^^^^^^^^┤  ^^^┤ ^^┤ ^^^^^^^^^^^^^^^┴─ 0 to 8-byte hexadecimal frame payload
+///                  │     │   └─ 11-bit (3 digits) or 29-bit (8 digits) hexadecimal identifier
+///                  │     └─ Can Interface - always can0
+///                  └─ Time of capture (in seconds since boot, microsecond resolution)
 /// ```
-pub fn frame_to_log(frame: &Frame) -> String<LOG_LINE_LEN> {
-    let _empty = bxcan::Data::empty();
-    let frame_data = frame.data().unwrap_or(&_empty);
-
-    formatted_string::<LOG_LINE_LEN>(format_args!(
-        "({:010}.000000) can0 {:03X}#{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}\n",
-        Mono::now().ticks(),
-        match frame.id() {
-            bxcan::Id::Standard(n) => n.as_raw() as u32,
-            bxcan::Id::Extended(n) => n.as_raw(),
-        },
-        frame_data.get(0).unwrap_or(&0xFF),
-        frame_data.get(1).unwrap_or(&0xFF),
-        frame_data.get(2).unwrap_or(&0xFF),
-        frame_data.get(3).unwrap_or(&0xFF),
-        frame_data.get(4).unwrap_or(&0xFF),
-        frame_data.get(5).unwrap_or(&0xFF),
-        frame_data.get(6).unwrap_or(&0xFF),
-        frame_data.get(7).unwrap_or(&0xFF),
+///
+/// `transmitted` frames are followed by the `T` direction flag of `candump -x`
+/// (`001#0123456789ABCDEF T`), which `canplayer` ignores.
+///
+/// Remote frames are written as `R` followed by their length (`001#R4`).
+pub fn frame_to_log(frame: &Frame, ticks: u32, transmitted: bool) -> String<LOG_LINE_LEN> {
+    frame_to_log_at(
+        frame,
+        ticks as u64 * 1_000_000 / TICK_RATE as u64,
+        transmitted,
+    )
+}
+
+/// Same as [`frame_to_log()`], for a frame captured `micros` microseconds after boot, such as a
+/// frame read from another log file.
+pub fn frame_to_log_at(frame: &Frame, micros: u64, transmitted: bool) -> String<LOG_LINE_LEN> {
+    let mut log_line = formatted_string::<LOG_LINE_LEN>(format_args!(
+        "({:010}.{:06}) can0 ",
+        micros / 1_000_000,
+        micros % 1_000_000,
     ))
-    .expect("LOG_LINE_LEN should be large enough.")
+    .expect("LOG_LINE_LEN should be large enough.");
+
+    match frame.id() {
+        bxcan::Id::Standard(n) => write!(log_line, "{:03X}#", n.as_raw()),
+        bxcan::Id::Extended(n) => write!(log_line, "{:08X}#", n.as_raw()),
+    }
+    .expect("LOG_LINE_LEN should be large enough.");
+    match frame.data() {
+        Some(data) => {
+            for byte in data.iter() {
+                write!(log_line, "{:02X}", byte).expect("LOG_LINE_LEN should be large enough.");
+            }
+        }
+        None => write!(log_line, "R{}", frame.dlc()).expect("LOG_LINE_LEN should be large enough."),
+    }
+    if transmitted {
+        log_line
+            .push_str(" T")
+            .expect("LOG_LINE_LEN should be large enough.");
+    }
+    log_line
+        .push('\n')
+        .expect("LOG_LINE_LEN should be large enough.");
+
+    log_line
 }
 
 /// Indexes the content of `dir` into the provided `content` [`Vec`].
@@ -213,8 +1241,12 @@ pub fn frame_to_log(frame: &Frame) -> String<LOG_LINE_LEN> {
 ///
 /// Returns [`Err`] in case of [`sdmmc::SdCardError`].
 ///
+/// Never inlined, so that its block buffer isn't kept in the frame of the SD tasks dispatcher
+/// while other SD tasks run.
+///
 /// # To-Do
 /// - [ ] Handle edge case of trying to add `.` to a full `content`.
+#[inline(never)]
 pub fn index_dir<const N: usize>(
     dir: &mut Directory,
     content: &mut Vec<(bool, ShortFileName), N>,
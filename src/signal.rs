@@ -0,0 +1,232 @@
+//! CAN signals decoding and DBC parsing abstractions.
+
+use core::str::FromStr;
+
+use bxcan::{ExtendedId, Frame, Id, StandardId};
+use heapless::String;
+
+/// A signal packed in the payload of a CAN frame.
+///
+/// Positions and byte orders follow the DBC conventions: for little-endian (Intel) signals
+/// `start_bit` is the least significant bit, for big-endian (Motorola) signals it is the most
+/// significant one.
+#[derive(Clone, Copy, Debug)]
+pub struct Signal {
+    /// Position of the first bit of the signal, bit 0 being the least significant bit of byte 0.
+    start_bit: u8,
+    /// Length of the signal in bits, between 1 and 64.
+    length: u8,
+    /// Byte order of the signal, `true` for big-endian (Motorola).
+    big_endian: bool,
+    /// Whether the raw value is a two's complement signed integer.
+    signed: bool,
+    /// Factor applied to the raw value.
+    factor: Decimal,
+    /// Offset added to the scaled value.
+    offset: Decimal,
+}
+
+/// A decimal number equal to `mantissa * 10^exponent`.
+///
+/// Signals are scaled with integers, core float operations taking several kilobytes of flash.
+#[derive(Clone, Copy, Debug)]
+struct Decimal {
+    mantissa: i32,
+    exponent: i8,
+}
+
+impl Decimal {
+    /// Returns `value * self` in hundredths, saturating on overflow.
+    fn scale(&self, value: i64) -> i64 {
+        let mut value = value.saturating_mul(self.mantissa as i64);
+        for _ in 0..(self.exponent as i32 + 2).unsigned_abs() {
+            value = if self.exponent < -2 {
+                value / 10
+            } else {
+                value.saturating_mul(10)
+            };
+        }
+        value
+    }
+}
+
+impl Signal {
+    /// Returns the unsigned and unscaled little-endian [`Signal`] made of the `length` bits
+    /// starting at `start_bit`.
+    pub fn bits(start_bit: u8, length: u8) -> Self {
+        Self {
+            start_bit,
+            length,
+            big_endian: false,
+            signed: false,
+            factor: Decimal {
+                mantissa: 1,
+                exponent: 0,
+            },
+            offset: Decimal {
+                mantissa: 0,
+                exponent: 0,
+            },
+        }
+    }
+
+    /// Decodes the physical value of the signal from a `frame`, in hundredths and saturated to
+    /// the range of an [`i32`].
+    ///
+    /// Returns [`None`] for remote frames or if the payload is too short to contain the signal.
+    pub fn decode(&self, frame: &Frame) -> Option<i32> {
+        let data = frame.data()?;
+        let mut payload = [0u8; 8];
+        payload[..data.len()].copy_from_slice(data);
+
+        let start = self.start_bit as usize;
+        let length = self.length as usize;
+        let raw = if self.big_endian {
+            // Position of the most significant bit when counting from the MSB of byte 0
+            let msb = (start / 8) * 8 + (7 - start % 8);
+            if msb + length > data.len() * 8 {
+                return None;
+            }
+            u64::from_be_bytes(payload) >> (64 - msb - length)
+        } else {
+            if start + length > data.len() * 8 {
+                return None;
+            }
+            u64::from_le_bytes(payload) >> start
+        };
+
+        let raw = raw & (u64::MAX >> (64 - length));
+        let value = if self.signed && raw >> (length - 1) & 1 == 1 {
+            (raw | u64::MAX << (length - 1) << 1) as i64
+        } else {
+            raw.min(i64::MAX as u64) as i64
+        };
+
+        let value = self
+            .factor
+            .scale(value)
+            .saturating_add(self.offset.scale(1));
+        Some(value.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+    }
+}
+
+/// A [`Signal`] defined in a DBC file.
+#[derive(Debug)]
+pub struct DbcSignal {
+    /// Identifier of the frames containing the signal.
+    pub id: Id,
+    /// Name of the signal, truncated to 16 characters.
+    pub name: String<16>,
+    /// Definition of the signal.
+    pub signal: Signal,
+}
+
+/// Parses a DBC message definition line, returning the identifier of the message.
+///
+/// Message lines have the format `BO_ <id> <name>: <dlc> <transmitter>`, with the bit 31 of `id`
+/// set for extended identifiers.
+pub fn parse_dbc_message(line: &str) -> Option<Id> {
+    let raw = u32::from_str(
+        line.trim_start()
+            .strip_prefix("BO_ ")?
+            .split_whitespace()
+            .next()?,
+    )
+    .ok()?;
+
+    if raw & 0x8000_0000 != 0 {
+        ExtendedId::new(raw & 0x1FFF_FFFF).map(Id::Extended)
+    } else {
+        StandardId::new(raw as u16).map(Id::Standard)
+    }
+}
+
+/// Parses a DBC signal definition line, returning the name and definition of the signal.
+///
+/// Signal lines have the format
+/// `SG_ <name> [<multiplexing>] : <start>|<length>@<order><sign> (<factor>,<offset>) ...`.
+///
+/// Multiplexed signals (`m<value>` indicator) are rejected, the multiplexer not being tracked they
+/// would be decoded from frames not containing them. Multiplexer signals (`M` indicator) are kept.
+pub fn parse_dbc_signal(line: &str) -> Option<(String<16>, Signal)> {
+    let (name, definition) = line.trim_start().strip_prefix("SG_ ")?.split_once(':')?;
+    let mut name = name.split_whitespace();
+    let (name, multiplexing) = (name.next()?, name.next());
+    if multiplexing.is_some_and(|m| m.starts_with('m')) {
+        return None;
+    }
+    let mut fields = definition.split_whitespace();
+
+    let (start_bit, rest) = fields.next()?.split_once('|')?;
+    let (length, format) = rest.split_once('@')?;
+    let length = u8::from_str(length).ok().filter(|l| (1..=64).contains(l))?;
+    let big_endian = match format.get(..1)? {
+        "0" => true,
+        "1" => false,
+        _ => return None,
+    };
+    let signed = format.get(1..)? == "-";
+
+    let (factor, offset) = fields
+        .next()?
+        .strip_prefix('(')?
+        .strip_suffix(')')?
+        .split_once(',')?;
+
+    Some((
+        name.get(..name.len().min(16))
+            .and_then(|n| String::from_str(n).ok())?,
+        Signal {
+            start_bit: u8::from_str(start_bit).ok().filter(|s| *s < 64)?,
+            length,
+            big_endian,
+            signed,
+            factor: parse_decimal(factor)?,
+            offset: parse_decimal(offset)?,
+        },
+    ))
+}
+
+/// Parses a DBC decimal number such as `-0.25` or `1E-005`.
+///
+/// Significant digits past the precision of an [`i32`] are dropped. Returns [`None`] if `number`
+/// isn't a decimal number.
+fn parse_decimal(number: &str) -> Option<Decimal> {
+    let (number, exponent) = match number.split_once(['e', 'E']) {
+        Some((number, exponent)) => (
+            number,
+            i8::from_str(exponent.strip_prefix('+').unwrap_or(exponent)).ok()?,
+        ),
+        None => (number, 0),
+    };
+    let (negative, number) = match number.strip_prefix('-') {
+        Some(number) => (true, number),
+        None => (false, number.strip_prefix('+').unwrap_or(number)),
+    };
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+
+    let mut mantissa = 0i32;
+    let mut exponent = exponent;
+    for (i, digit) in whole.bytes().chain(fraction.bytes()).enumerate() {
+        if !digit.is_ascii_digit() {
+            return None;
+        }
+        // Digits past the precision of an i32 are dropped, only their magnitude is kept
+        if mantissa < i32::MAX / 10 - 9 {
+            mantissa = mantissa * 10 + (digit - b'0') as i32;
+            if i >= whole.len() {
+                exponent = exponent.checked_sub(1)?;
+            }
+        } else if i < whole.len() {
+            exponent = exponent.checked_add(1)?;
+        }
+    }
+
+    Some(Decimal {
+        mantissa: if negative { -mantissa } else { mantissa },
+        exponent,
+    })
+}
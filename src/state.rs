@@ -1,16 +1,22 @@
 //! State, screens and inputs relevant abstractions.
 
-use core::fmt::Debug;
-
+use bxcan::Frame;
 use embedded_sdmmc::ShortFileName;
 use heapless::Vec;
+use rtic::Mutex;
+use rtic_monotonics::{fugit::ExtU32, Monotonic};
 use rtt_target::rprintln;
 
 use crate::{
-    app::{MAX_SD_INDEX_AMOUNT, MAX_SD_INDEX_DEPTH},
+    app::{Mono, MAX_SD_INDEX_AMOUNT, MAX_SD_INDEX_DEPTH, PAUSE_POLL_DELAY_MS},
+    bisect::Bisect,
     buttons::Button,
     can::{Bitrate, EmissionMode},
+    filter::{step_digit, IdFilter, MAX_ID},
+    monitor::FrameMonitor,
+    range::{LogRange, MAX_BOUND},
     render::*,
+    sd::{BadLines, LogFormat},
 };
 
 /// The firmware state manager.
@@ -18,6 +24,9 @@ use crate::{
 /// It wraps a [`Display`] (the physical hardware), a [`Screen`] (what is being shown on the
 /// `display`) and a [`State`]. This struct is responsible for rendering (trough
 /// [`render()`][StateManager::render()]), input handling is delegated to the [`Screen`] struct.
+///
+/// The [`FrameMonitor`] used by the live pages is a separate shared resource, locked only while
+/// drawing so that the CAN tasks aren't delayed by the display flush.
 pub struct StateManager {
     /// Physical display to render to.
     display: Display,
@@ -39,12 +48,27 @@ impl StateManager {
 
     /// Renders the [`current_screen`][Self::current_screen] to the [`display`][Self::display].
     ///
+    /// The `monitor` is [synced][Self::sync_monitor()] and the screen drawn while it is locked, the
+    /// display being flushed once it is released.
+    ///
+    /// This also prints a debug message with the [`current_screen`][Self::current_screen]. The
+    /// [`state`][Self::state] isn't printed, its formatting code wouldn't fit in flash.
+    pub fn render(&mut self, monitor: &mut impl Mutex<T = &'static mut FrameMonitor>) {
+        monitor.lock(|monitor| {
+            self.sync_monitor(monitor);
+            self.draw(monitor);
+        });
+        self.display.flush().expect("Display should be responding.");
+
+        rprintln!("{:?}", self.current_screen);
+    }
+
+    /// Draws the [`current_screen`][Self::current_screen] in the buffer of the
+    /// [`display`][Self::display].
+    ///
     /// This is done by matching the [`current_screen`][Self::current_screen] and calling the
     /// appropriate `draw` function from the [`render`][crate::render] module.
-    ///
-    /// This also prints a debug message with the [`current_screen`][Self::current_screen] and
-    /// [`state`][Self::state].
-    pub fn render(&mut self) {
+    fn draw(&mut self, monitor: &FrameMonitor) {
         self.display.clear_buffer();
         match &self.current_screen {
             Screen::Home { selected_item } => draw_home(&mut self.display, selected_item),
@@ -59,6 +83,33 @@ impl StateManager {
                 },
                 *selected_index,
             ),
+            Screen::Emission | Screen::Capture
+                if self.state.running && self.state.live_page == LivePage::Frames =>
+            {
+                draw_live_frames(
+                    &mut self.display,
+                    matches!(self.current_screen, Screen::Emission),
+                    self.state.live_frozen,
+                    monitor.recent_frames(),
+                )
+            }
+            Screen::Emission | Screen::Capture
+                if self.state.running && self.state.live_page == LivePage::Sniffer =>
+            {
+                draw_sniffer(
+                    &mut self.display,
+                    self.state.live_frozen,
+                    monitor.sniffed_ids(),
+                    self.state.sniffer_selected,
+                    Mono::now().ticks(),
+                )
+            }
+            Screen::Emission | Screen::Capture
+                if self.state.running
+                    && matches!(self.state.live_page, LivePage::Plot | LivePage::PlotLength) =>
+            {
+                draw_plot(&mut self.display, self.state.live_frozen, monitor.plot())
+            }
             Screen::Emission => draw_emission(
                 &mut self.display,
                 self.state
@@ -66,10 +117,16 @@ impl StateManager {
                     .last()
                     .expect("Path should have been filled."),
                 self.state.running,
+                self.state.paused,
                 self.state.emission_count,
                 &self.state.bitrate,
                 &self.state.emission_mode,
                 self.state.success_count,
+                self.state.error,
+                self.state.bisect.as_ref(),
+                (self.state.replay_mode == ReplayMode::Step).then_some(self.state.step_index),
+                self.state.step_sent.as_ref(),
+                self.state.validation.as_ref(),
             ),
             Screen::Capture => draw_capture(
                 &mut self.display,
@@ -77,26 +134,88 @@ impl StateManager {
                 self.state.running,
                 &self.state.bitrate,
                 self.state.capture_silent,
+                self.state.capture_logging,
                 self.state.success_count,
             ),
-            Screen::EmissionSettings { selected_item } => draw_emission_settings(
+            Screen::EmissionSettings {
+                selected_item,
+                step,
+            } => draw_emission_settings(
                 &mut self.display,
                 selected_item,
+                *step,
                 &self.state.bitrate,
                 &self.state.emission_mode,
+                &self.state.replay_mode,
+                &self.state.replay_range,
+            ),
+            Screen::FilterSettings {
+                selected_item,
+                entry,
+                step,
+            } => draw_filter_settings(
+                &mut self.display,
+                selected_item,
+                &self.state.id_filter,
+                *entry,
+                *step,
+            ),
+            Screen::CaptureSettings { selected_item } => draw_capture_settings(
+                &mut self.display,
+                selected_item,
+                &self.state.bitrate,
+                self.state.capture_silent,
+                self.state.capture_logging,
+                &self.state.capture_format,
             ),
         }
-        self.display.flush().expect("Display should be responding.");
-
-        rprintln!("{:#?}", self);
     }
 
     /// Propagates a button press and renders the screen.
     ///
     /// See [`Screen::press()`] for implementation details.
-    pub fn press(&mut self, button: Button) {
+    pub fn press(
+        &mut self,
+        button: Button,
+        monitor: &mut impl Mutex<T = &'static mut FrameMonitor>,
+    ) {
         self.current_screen.press(button, &mut self.state);
-        self.render();
+        self.render(monitor);
+    }
+
+    /// Updates the `monitor` and the [`State`] to match each other.
+    ///
+    /// The frames counted by the CAN tasks are added to [`State::success_count`], and recording
+    /// is frozen along with the live pages. While on the [`Plot`][LivePage::Plot]
+    /// live page, the plotted identifier is the one selected on the [`Sniffer`][LivePage::Sniffer]
+    /// live page when entering it, or the first one seen afterwards. Plotting is stopped on every
+    /// other page.
+    fn sync_monitor(&mut self, monitor: &mut FrameMonitor) {
+        self.state.success_count += core::mem::take(&mut monitor.frames_count);
+        monitor.frozen = self.state.live_frozen;
+        if !self.state.running
+            || !matches!(self.state.live_page, LivePage::Plot | LivePage::PlotLength)
+        {
+            monitor.stop_plot();
+            return;
+        }
+
+        let sniffed_ids = monitor.sniffed_ids();
+        let id = match monitor.plot() {
+            Some(plot) => Some(plot.id),
+            None => sniffed_ids
+                .get(
+                    self.state
+                        .sniffer_selected
+                        .min(sniffed_ids.len().saturating_sub(1)),
+                )
+                .map(|sniffed| sniffed.frame.id()),
+        };
+
+        if let Some(id) = id {
+            (self.state.plot_signal, self.state.plot_length) =
+                monitor.select_plot(id, self.state.plot_signal, self.state.plot_length);
+        }
     }
 
     /// Returns a reference to the [`current_screen`][Self::current_screen].
@@ -105,21 +224,21 @@ impl StateManager {
     }
 }
 
-impl Debug for StateManager {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(
-            f,
-            "{{
-    current_screen: {:#?},
-    state: {:#?},
-}}",
-            self.current_screen,
-            State {
-                dir_path: self.state.dir_path.clone(),
-                dir_content: Vec::new(),
-                ..self.state
+/// Returns whether the emission is still running, waiting here while it is
+/// [`paused`][State::paused].
+///
+/// The second value tells whether the emission was paused, for timed emissions to reschedule their
+/// frames.
+pub async fn wait_while_paused(state_manager: &mut impl Mutex<T = StateManager>) -> (bool, bool) {
+    let mut was_paused = false;
+    loop {
+        match state_manager.lock(|sm| (sm.state.running, sm.state.paused)) {
+            (true, true) => {
+                was_paused = true;
+                Mono::delay(PAUSE_POLL_DELAY_MS.millis()).await;
             }
-        )
+            (running, _) => return (running, was_paused),
+        }
     }
 }
 
@@ -142,6 +261,17 @@ pub enum Screen {
     EmissionSettings {
         /// Currently selected setting.
         selected_item: EmissionSettingsItem,
+        /// Value added to or subtracted from the edited range bound, a power of 10.
+        step: u32,
+    },
+    /// Settings screen for the emission identifier filter.
+    FilterSettings {
+        /// Currently selected setting.
+        selected_item: FilterSettingsItem,
+        /// Index of the edited range in [`IdFilter::ranges()`], the number of ranges for a new one.
+        entry: usize,
+        /// Value added to or subtracted from the edited bound, a power of 16.
+        step: u32,
     },
     /// Directory selection screen for capture.
     CaptureSelection {
@@ -150,6 +280,11 @@ pub enum Screen {
     },
     /// Capture screen.
     Capture,
+    /// Settings screen for capture mode, displayed once the capture directory is selected.
+    CaptureSettings {
+        /// Currently selected setting.
+        selected_item: CaptureSettingsItem,
+    },
 }
 
 /// Data-less equivalent of [`Screen`].
@@ -160,8 +295,10 @@ enum ScreenVariant {
     EmissionSelection,
     Emission,
     EmissionSettings,
+    FilterSettings,
     CaptureSelection,
     Capture,
+    CaptureSettings,
 }
 
 impl Screen {
@@ -180,9 +317,18 @@ impl Screen {
             ScreenVariant::Emission => Self::Emission,
             ScreenVariant::EmissionSettings => Self::EmissionSettings {
                 selected_item: EmissionSettingsItem::Bitrate,
+                step: 1,
+            },
+            ScreenVariant::FilterSettings => Self::FilterSettings {
+                selected_item: FilterSettingsItem::Filter,
+                entry: 0,
+                step: 0x1,
             },
             ScreenVariant::CaptureSelection => Self::CaptureSelection { selected_index: 0 },
             ScreenVariant::Capture => Self::Capture,
+            ScreenVariant::CaptureSettings => Self::CaptureSettings {
+                selected_item: CaptureSettingsItem::Bitrate,
+            },
         }
     }
 
@@ -250,12 +396,38 @@ impl Screen {
                 }
             },
             Self::Emission => match (button, state.running) {
-                (Button::Ok, _) => {
-                    state.running = !state.running;
-                    if state.running {
-                        state.success_count = 0
+                (Button::Ok, false) => {
+                    state.running = true;
+                    state.reset_live_page();
+                    state.success_count = 0;
+                    state.error = None;
+                    state.validation = None;
+                    if state.replay_mode == ReplayMode::Bisect
+                        && state.bisect.as_ref().is_none_or(|b| b.is_saved())
+                    {
+                        state.bisect = Some(Bisect::new());
                     }
                 }
+                (Button::Ok, true) => state.paused = !state.paused,
+                (Button::Right, true) => state.live_page.increment(),
+                (Button::Left, true) if state.live_page != LivePage::Summary => {
+                    state.live_frozen = !state.live_frozen
+                }
+                (Button::Left, true) => {
+                    state.running = false;
+                    state.paused = false;
+                    state.reset_live_page();
+                }
+                (Button::Up, true) => state.scroll_live_page(true),
+                (Button::Down, true) => state.scroll_live_page(false),
+                (Button::Up, false) if state.answer_bisect(true) => {}
+                (Button::Down, false) if state.answer_bisect(false) => {}
+                (Button::Up, false) if state.replay_mode == ReplayMode::Step => {
+                    state.step_index = state.step_index.saturating_add(1)
+                }
+                (Button::Down, false) if state.replay_mode == ReplayMode::Step => {
+                    state.step_index = state.step_index.saturating_sub(1)
+                }
                 (Button::Up, false) => {
                     state.emission_count = state.emission_count.saturating_add(1)
                 }
@@ -271,33 +443,97 @@ impl Screen {
                         selected_item: HomeItem::Emit,
                     }
                 }
-                _ => {}
             },
-            Self::EmissionSettings { selected_item } => match button {
+            Self::EmissionSettings {
+                selected_item,
+                step,
+            } => match button {
                 Button::Ok => *self = Self::default_variant(ScreenVariant::Emission),
                 Button::Up => selected_item.decrement(),
+                Button::Down if matches!(selected_item, EmissionSettingsItem::To) => {
+                    *self = Self::default_variant(ScreenVariant::FilterSettings)
+                }
                 Button::Down => selected_item.increment(),
                 Button::Right => match selected_item {
                     EmissionSettingsItem::Bitrate => state.bitrate.increment(),
                     EmissionSettingsItem::Mode => state.emission_mode.increment(),
+                    EmissionSettingsItem::Replay => {
+                        state.replay_mode.increment();
+                        state.reset_replay();
+                    }
+                    EmissionSettingsItem::Range => {
+                        state.replay_range.unit.increment();
+                        state.replay_range.from = 0;
+                        state.replay_range.to = 0;
+                    }
+                    EmissionSettingsItem::Step => *step = step_digit(*step, true, 10, MAX_BOUND),
+                    EmissionSettingsItem::From => state.replay_range.step_bound(false, *step, true),
+                    EmissionSettingsItem::To => state.replay_range.step_bound(true, *step, true),
                 },
                 Button::Left => match selected_item {
                     EmissionSettingsItem::Bitrate => state.bitrate.decrement(),
                     EmissionSettingsItem::Mode => state.emission_mode.decrement(),
+                    EmissionSettingsItem::Replay => {
+                        state.replay_mode.decrement();
+                        state.reset_replay();
+                    }
+                    EmissionSettingsItem::Range => {
+                        state.replay_range.unit.decrement();
+                        state.replay_range.from = 0;
+                        state.replay_range.to = 0;
+                    }
+                    EmissionSettingsItem::Step => *step = step_digit(*step, false, 10, MAX_BOUND),
+                    EmissionSettingsItem::From => {
+                        state.replay_range.step_bound(false, *step, false)
+                    }
+                    EmissionSettingsItem::To => state.replay_range.step_bound(true, *step, false),
                 },
             },
+            Self::FilterSettings {
+                selected_item,
+                entry,
+                step,
+            } => match button {
+                Button::Ok => *self = Self::default_variant(ScreenVariant::Emission),
+                Button::Up if matches!(selected_item, FilterSettingsItem::Filter) => {
+                    *self = Self::EmissionSettings {
+                        selected_item: EmissionSettingsItem::To,
+                        step: 1,
+                    }
+                }
+                Button::Up => selected_item.decrement(),
+                Button::Down => selected_item.increment(),
+                Button::Right | Button::Left => {
+                    let up = matches!(button, Button::Right);
+                    match selected_item {
+                        FilterSettingsItem::Filter if up => state.id_filter.mode.increment(),
+                        FilterSettingsItem::Filter => state.id_filter.mode.decrement(),
+                        FilterSettingsItem::Entry if up => {
+                            *entry = (*entry + 1).min(state.id_filter.ranges().len())
+                        }
+                        FilterSettingsItem::Entry => *entry = entry.saturating_sub(1),
+                        FilterSettingsItem::Step => *step = step_digit(*step, up, 16, MAX_ID),
+                        FilterSettingsItem::First => {
+                            state.id_filter.step_range(*entry, false, *step, up)
+                        }
+                        FilterSettingsItem::Last => {
+                            state.id_filter.step_range(*entry, true, *step, up)
+                        }
+                    }
+                }
+            },
             Self::CaptureSelection { selected_index } => match button {
                 Button::Ok => match &state.dir_content[*selected_index] {
                     (true, parent_dir) if parent_dir == &ShortFileName::parent_dir() => {
                         state.dir_path.pop();
-                        *self = Self::default_variant(ScreenVariant::Capture);
+                        *self = Self::default_variant(ScreenVariant::CaptureSettings);
                     }
                     (true, this_dir) if this_dir == &ShortFileName::this_dir() => {
-                        *self = Self::default_variant(ScreenVariant::Capture);
+                        *self = Self::default_variant(ScreenVariant::CaptureSettings);
                     }
                     (true, dir_name) => {
                         state.dir_path.push(dir_name.clone()).unwrap();
-                        *self = Self::default_variant(ScreenVariant::Capture);
+                        *self = Self::default_variant(ScreenVariant::CaptureSettings);
                     }
                     (false, _) => unreachable!("dir_content will only contain dirs."),
                 },
@@ -348,10 +584,17 @@ impl Screen {
             Self::Capture => match (button, state.running) {
                 (Button::Ok, _) => {
                     state.running = !state.running;
+                    state.reset_live_page();
                     if state.running {
                         state.success_count = 0
                     }
                 }
+                (Button::Right, true) => state.live_page.increment(),
+                (Button::Left, true) if state.live_page != LivePage::Summary => {
+                    state.live_frozen = !state.live_frozen
+                }
+                (Button::Up, true) => state.scroll_live_page(true),
+                (Button::Down, true) => state.scroll_live_page(false),
                 (Button::Up, false) => state.bitrate.increment(),
                 (Button::Down, false) => state.bitrate.decrement(),
                 (Button::Right, false) => state.capture_silent = !state.capture_silent,
@@ -363,6 +606,23 @@ impl Screen {
                 }
                 _ => {}
             },
+            Self::CaptureSettings { selected_item } => match button {
+                Button::Ok => *self = Self::default_variant(ScreenVariant::Capture),
+                Button::Up => selected_item.decrement(),
+                Button::Down => selected_item.increment(),
+                Button::Right => match selected_item {
+                    CaptureSettingsItem::Bitrate => state.bitrate.increment(),
+                    CaptureSettingsItem::Silent => state.capture_silent = !state.capture_silent,
+                    CaptureSettingsItem::Logging => state.capture_logging = !state.capture_logging,
+                    CaptureSettingsItem::Format => state.capture_format.increment(),
+                },
+                Button::Left => match selected_item {
+                    CaptureSettingsItem::Bitrate => state.bitrate.decrement(),
+                    CaptureSettingsItem::Silent => state.capture_silent = !state.capture_silent,
+                    CaptureSettingsItem::Logging => state.capture_logging = !state.capture_logging,
+                    CaptureSettingsItem::Format => state.capture_format.decrement(),
+                },
+            },
         }
     }
 }
@@ -378,14 +638,44 @@ pub struct State {
     ///
     /// `0` means infinite repetitions.
     pub emission_count: u8,
+    /// Way log files are replayed in emission mode.
+    pub replay_mode: ReplayMode,
+    /// Progress of the binary-search replay, while in [`ReplayMode::Bisect`].
+    ///
+    /// It is started when the emission is, and kept between emissions until its result is saved.
+    pub bisect: Option<Bisect>,
+    /// Part of the log files replayed in [`ReplayMode::Normal`] and [`ReplayMode::Record`].
+    pub replay_range: LogRange,
+    /// Filter of the frames replayed from log files, see [`IdFilter`].
+    pub id_filter: IdFilter,
+    /// Index in the log file of the next frame sent, while in [`ReplayMode::Step`].
+    pub step_index: u32,
+    /// Last frame sent while in [`ReplayMode::Step`], with its index in the log file.
+    pub step_sent: Option<(u32, Frame)>,
+    /// Bad lines found by the last validation of the log file, in [`ReplayMode::Validate`].
+    ///
+    /// It is kept until the next emission, the number of frames read being in
+    /// [`success_count`][Self::success_count].
+    pub validation: Option<BadLines>,
     /// CAN bus silent flag for capture mode.
     pub capture_silent: bool,
+    /// SD logging flag for capture mode.
+    ///
+    /// If not set, received frames are only shown on the live pages and not saved.
+    pub capture_logging: bool,
+    /// Format of the log files written in capture mode.
+    pub capture_format: LogFormat,
     /// System running flag.
     ///
     /// This flags indicates whether some I/O is in progress, it can be interfacing with the CAN
     /// bus and/or the SD card. Changing this flag should be followed by a trigger of the
     /// [`state_updater()`][crate::app::state_updater].
     pub running: bool,
+    /// Pause flag of the emission.
+    ///
+    /// While set, [`running`][Self::running] stays set but no frame is sent, the emission
+    /// resuming where it was once cleared.
+    pub paused: bool,
     /// Success count of last I/O operation.
     ///
     /// This often contains number of CAN frames sent or captured.
@@ -402,6 +692,29 @@ pub struct State {
     /// The [`Vec`] is only filled while in [`EmissionSelection`][`Screen::EmissionSelection`] or
     /// [`CaptureSelection`][`Screen::CaptureSelection`].
     pub dir_content: Vec<(bool, ShortFileName), MAX_SD_INDEX_AMOUNT>,
+    /// Page displayed while an emission or capture is running.
+    pub live_page: LivePage,
+    /// Freeze flag of the live pages.
+    ///
+    /// While set, the [`FrameMonitor`] stops recording frames so that the displayed ones can be
+    /// read at leisure.
+    pub live_frozen: bool,
+    /// Index of the selected identifier on the [`Sniffer`][LivePage::Sniffer] live page.
+    ///
+    /// It can go beyond the number of identifiers seen, it is clamped when rendering.
+    pub sniffer_selected: usize,
+    /// Index of the signal plotted on the [`Plot`][LivePage::Plot] live page.
+    ///
+    /// See [`FrameMonitor::select_plot()`] for the list of signals indexed.
+    pub plot_signal: usize,
+    /// Length of the bits fields offered on the [`Plot`][LivePage::Plot] live page, set on the
+    /// [`PlotLength`][LivePage::PlotLength] one.
+    pub plot_length: usize,
+    /// Error of the last emission, if it couldn't be started.
+    ///
+    /// The values are in format `(message: &str, line_number: u32)`, `line_number` being `0` for
+    /// errors that don't relate to a line of the emitted file.
+    pub error: Option<(&'static str, u32)>,
 }
 
 impl State {
@@ -411,11 +724,27 @@ impl State {
             bitrate: Bitrate::Br125kbps,
             emission_mode: EmissionMode::AwaitACK,
             emission_count: 1,
+            replay_mode: ReplayMode::Normal,
+            bisect: None,
+            replay_range: LogRange::new(),
+            id_filter: IdFilter::new(),
+            step_index: 0,
+            step_sent: None,
+            validation: None,
             capture_silent: false,
+            capture_logging: true,
+            capture_format: LogFormat::Log,
             running: false,
+            paused: false,
             success_count: 0,
             dir_path: Vec::new(),
             dir_content: Vec::new(),
+            live_page: LivePage::Summary,
+            live_frozen: false,
+            sniffer_selected: 0,
+            plot_signal: 0,
+            plot_length: 8,
+            error: None,
         }
     }
 
@@ -424,13 +753,72 @@ impl State {
     /// The reset variables are so because considered too context dependent.
     ///
     /// Are reset : [`emission_count`][Self::emission_count], [`success_count`][Self::success_count],
-    /// [`dir_path`][Self::dir_path], [`dir_content`][Self::dir_content].
+    /// [`dir_path`][Self::dir_path], [`dir_content`][Self::dir_content], [`error`][Self::error],
+    /// [`bisect`][Self::bisect], [`step_index`][Self::step_index],
+    /// [`step_sent`][Self::step_sent], [`validation`][Self::validation].
     pub fn soft_reset(&mut self) {
         self.emission_count = 1;
         self.success_count = 0;
+        self.error = None;
+        self.reset_replay();
         self.dir_path = Vec::new();
         self.dir_content = Vec::new();
     }
+
+    /// Goes back to the [`Summary`][LivePage::Summary] live page, unfreezes it and resets the
+    /// sniffer and plot selections.
+    pub fn reset_live_page(&mut self) {
+        self.live_page = LivePage::Summary;
+        self.live_frozen = false;
+        self.sniffer_selected = 0;
+        self.plot_signal = 0;
+        self.plot_length = 8;
+    }
+
+    /// Resets the progress of the [`bisect`][Self::bisect] and of the step-by-step emission, and
+    /// the result of the last [`validation`][Self::validation].
+    pub fn reset_replay(&mut self) {
+        self.bisect = None;
+        self.step_index = 0;
+        self.step_sent = None;
+        self.validation = None;
+    }
+
+    /// Answers whether the effect was seen during the last replay of the [`bisect`][Self::bisect].
+    ///
+    /// Once the frames are isolated, [`running`][Self::running] is set to save them. Returns
+    /// `false` if no answer is expected.
+    pub fn answer_bisect(&mut self, effect_seen: bool) -> bool {
+        let Some(bisect) = self.bisect.as_mut().filter(|b| b.is_awaiting_answer()) else {
+            return false;
+        };
+
+        bisect.answer(effect_seen);
+        if bisect.result().is_some() {
+            self.running = true;
+            self.success_count = 0;
+        }
+        true
+    }
+
+    /// Moves the selection of the current live page up or down.
+    ///
+    /// Selects the identifier on the [`Sniffer`][LivePage::Sniffer] page, the signal on the
+    /// [`Plot`][LivePage::Plot] page and the length of the bits fields on the
+    /// [`PlotLength`][LivePage::PlotLength] page, does nothing on other pages.
+    pub fn scroll_live_page(&mut self, up: bool) {
+        let selection = match self.live_page {
+            LivePage::Sniffer => &mut self.sniffer_selected,
+            LivePage::Plot => &mut self.plot_signal,
+            LivePage::PlotLength => &mut self.plot_length,
+            LivePage::Summary | LivePage::Frames => return,
+        };
+        *selection = if up {
+            selection.saturating_sub(1)
+        } else {
+            selection.saturating_add(1)
+        };
+    }
 }
 
 /// Items displayed on [`Screen::Home`].
@@ -441,24 +829,169 @@ pub enum HomeItem {
 }
 
 /// Items displayed on [`Screen::EmissionSettings`].
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum EmissionSettingsItem {
     Bitrate,
     Mode,
+    Replay,
+    Range,
+    Step,
+    From,
+    To,
 }
 
 impl EmissionSettingsItem {
     /// Increments `self` to next [`EmissionSettingsItem`].
     pub fn increment(&mut self) {
         *self = match self {
-            Self::Bitrate | Self::Mode => Self::Mode,
+            Self::Bitrate => Self::Mode,
+            Self::Mode => Self::Replay,
+            Self::Replay => Self::Range,
+            Self::Range => Self::Step,
+            Self::Step => Self::From,
+            Self::From | Self::To => Self::To,
         }
     }
 
     /// Decrements `self` to previous [`EmissionSettingsItem`].
     pub fn decrement(&mut self) {
         *self = match self {
+            Self::To => Self::From,
+            Self::From => Self::Step,
+            Self::Step => Self::Range,
+            Self::Range => Self::Replay,
+            Self::Replay => Self::Mode,
             Self::Mode | Self::Bitrate => Self::Bitrate,
         }
     }
 }
+
+/// Items displayed on [`Screen::FilterSettings`].
+#[derive(Clone, Copy, Debug)]
+pub enum FilterSettingsItem {
+    Filter,
+    Entry,
+    Step,
+    First,
+    Last,
+}
+
+impl FilterSettingsItem {
+    /// Increments `self` to next [`FilterSettingsItem`].
+    pub fn increment(&mut self) {
+        *self = match self {
+            Self::Filter => Self::Entry,
+            Self::Entry => Self::Step,
+            Self::Step => Self::First,
+            Self::First | Self::Last => Self::Last,
+        }
+    }
+
+    /// Decrements `self` to previous [`FilterSettingsItem`].
+    pub fn decrement(&mut self) {
+        *self = match self {
+            Self::Last => Self::First,
+            Self::First => Self::Step,
+            Self::Step => Self::Entry,
+            Self::Entry | Self::Filter => Self::Filter,
+        }
+    }
+}
+
+/// Way log files are replayed in emission mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReplayMode {
+    /// Frames are sent [`State::emission_count`] times.
+    Normal,
+    /// Frames are sent [`State::emission_count`] times, and both the frames sent and received are
+    /// recorded in a transcript.
+    Record,
+    /// Frames are sent by halves to isolate the ones triggering an effect, see [`Bisect`].
+    Bisect,
+    /// A single frame is sent per emission, the one at [`State::step_index`].
+    Step,
+    /// No frame is sent, the log file is read to find the lines that can't be parsed (see
+    /// [`State::validation`]).
+    Validate,
+}
+
+impl ReplayMode {
+    /// Increments `self` to next [`ReplayMode`], wrapping around.
+    pub fn increment(&mut self) {
+        *self = match self {
+            Self::Normal => Self::Record,
+            Self::Record => Self::Bisect,
+            Self::Bisect => Self::Step,
+            Self::Step => Self::Validate,
+            Self::Validate => Self::Normal,
+        }
+    }
+
+    /// Decrements `self` to previous [`ReplayMode`], wrapping around.
+    pub fn decrement(&mut self) {
+        *self = match self {
+            Self::Record => Self::Normal,
+            Self::Bisect => Self::Record,
+            Self::Step => Self::Bisect,
+            Self::Validate => Self::Step,
+            Self::Normal => Self::Validate,
+        }
+    }
+}
+
+/// Items displayed on [`Screen::CaptureSettings`].
+#[derive(Clone, Copy, Debug)]
+pub enum CaptureSettingsItem {
+    Bitrate,
+    Silent,
+    Logging,
+    Format,
+}
+
+impl CaptureSettingsItem {
+    /// Increments `self` to next [`CaptureSettingsItem`].
+    pub fn increment(&mut self) {
+        *self = match self {
+            Self::Bitrate => Self::Silent,
+            Self::Silent => Self::Logging,
+            Self::Logging | Self::Format => Self::Format,
+        }
+    }
+
+    /// Decrements `self` to previous [`CaptureSettingsItem`].
+    pub fn decrement(&mut self) {
+        *self = match self {
+            Self::Format => Self::Logging,
+            Self::Logging => Self::Silent,
+            Self::Silent | Self::Bitrate => Self::Bitrate,
+        }
+    }
+}
+
+/// Pages available while an emission or a capture is running.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LivePage {
+    /// Summary of the running operation (default emission or capture screen).
+    Summary,
+    /// Scrolling view of the last frames sent or received.
+    Frames,
+    /// Table of the identifiers seen with their statistics.
+    Sniffer,
+    /// Graph of a signal of the identifier selected on the sniffer page.
+    Plot,
+    /// Same graph as [`Plot`][LivePage::Plot], setting the length of the bits fields plotted.
+    PlotLength,
+}
+
+impl LivePage {
+    /// Increments `self` to next [`LivePage`], wrapping around.
+    pub fn increment(&mut self) {
+        *self = match self {
+            Self::Summary => Self::Frames,
+            Self::Frames => Self::Sniffer,
+            Self::Sniffer => Self::Plot,
+            Self::Plot => Self::PlotLength,
+            Self::PlotLength => Self::Summary,
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! PEAK TRC log files abstractions, as saved by PCAN-View.
+
+use bxcan::{Data, ExtendedId, Frame, Id, StandardId};
+use heapless::Vec;
+use rtt_target::rprintln;
+
+use crate::sd::{parse_micros, BadLines, LogLines, LogSource};
+
+/// Largest number of columns of a TRC file.
+const MAX_TRC_COLUMNS: usize = 12;
+
+/// Columns of version 1.0 files : number, time offset, identifier, length and data.
+const V1_0_COLUMNS: &[u8] = b"NOIlD";
+/// Columns of version 1.1 files, adding the direction (or event type) after the time offset.
+const V1_1_COLUMNS: &[u8] = b"NOdIlD";
+/// Columns of version 2.0 files, also the default ones of later versions.
+const V2_0_COLUMNS: &[u8] = b"NOTIdlD";
+
+/// An iterator over CAN frames contained in a PEAK TRC log file.
+///
+/// Versions 1.0, 1.1 and 2.x are read, the version being given by the `;$FILEVERSION` header
+/// line (1.0 if missing) and the layout of 2.x lines by the `;$COLUMNS` header line if any :
+/// ```text
+/// ;$FILEVERSION=1.1
+///      1)      1059.9  Rx         0300  8  00 00 00 00 04 00 00 00
+///      2)      1283.2  Rx     18EFC8F4  4  RTR
+///
+/// ;$FILEVERSION=2.0
+///       1      1059.900 DT     0300 Rx 8  00 00 00 00 04 00 00 00
+///       2      1283.231 RR 18EFC8F4 Rx 4
+/// ```
+///
+/// Identifiers of more than 4 digits are extended ones. Other comment lines and events other
+/// than CAN frames (errors, status...) are skipped, CAN FD frames with a warning.
+///
+/// # To-Do
+/// - [ ] Keep the version and columns of the header when seeking past it.
+pub struct TrcLogsIterator<S: LogSource> {
+    /// The lines of the log file that will be scanned for CAN frames.
+    lines: LogLines<S>,
+    /// Time of the last frame read, in microseconds since the start of the trace.
+    timestamp: u64,
+    /// Columns of the lines, as letters of the `;$COLUMNS` header line.
+    columns: Vec<u8, MAX_TRC_COLUMNS>,
+}
+
+impl<S: LogSource> TrcLogsIterator<S> {
+    /// Creates a new [`TrcLogsIterator`] from a given [`LogSource`].
+    pub fn new(log_file: S) -> Self {
+        Self {
+            lines: LogLines::new(log_file),
+            timestamp: 0,
+            columns: Vec::from_slice(V1_0_COLUMNS).expect("MAX_TRC_COLUMNS should be enough."),
+        }
+    }
+
+    /// Moves to the line starting `offset` bytes from the start of the log file, as returned by
+    /// [`offset()`][TrcLogsIterator::offset()].
+    pub fn seek(&mut self, offset: u32) -> Result<(), ()> {
+        self.lines.seek(offset)
+    }
+
+    /// Returns the offset in bytes of the next line to be read in the log file.
+    pub fn offset(&self) -> u32 {
+        self.lines.offset()
+    }
+
+    /// Returns the time of the last frame read, in seconds.
+    pub fn timestamp(&self) -> u32 {
+        (self.timestamp / 1_000_000) as u32
+    }
+
+    /// Returns the time of the last frame read, in microseconds.
+    pub fn timestamp_micros(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns the lines skipped so far because they couldn't be parsed.
+    pub fn bad_lines(&self) -> &BadLines {
+        self.lines.bad_lines()
+    }
+
+    /// Returns a mutable reference to the [`LogSource`] being read.
+    pub fn source_mut(&mut self) -> &mut S {
+        self.lines.source_mut()
+    }
+
+    /// Reads the version or the columns of the file from a header line, other lines being ignored.
+    fn parse_header(&mut self, comment: &str) {
+        if let Some(version) = comment.strip_prefix("$FILEVERSION=") {
+            let columns = match version.trim() {
+                "1.0" => V1_0_COLUMNS,
+                version if version.starts_with("1.") => V1_1_COLUMNS,
+                _ => V2_0_COLUMNS,
+            };
+            self.columns = Vec::from_slice(columns).expect("MAX_TRC_COLUMNS should be enough.");
+        } else if let Some(columns) = comment.strip_prefix("$COLUMNS=") {
+            self.columns = columns
+                .split(',')
+                .filter_map(|column| column.trim().bytes().next())
+                .take(MAX_TRC_COLUMNS)
+                .collect();
+        }
+    }
+
+    /// Parses a line of the file following its columns.
+    ///
+    /// Returns `Ok(None)` for events other than CAN frames, and [`Err`] if the line is invalid.
+    fn parse_line(&mut self, line: &str) -> Result<Option<Frame>, ()> {
+        let mut fields = line.split_whitespace();
+        let (mut micros, mut id, mut len, mut remote) = (None, None, None, false);
+
+        for column in self.columns.iter() {
+            if *column == b'D' {
+                break;
+            }
+            let field = fields.next().ok_or(())?;
+            match column {
+                b'O' => micros = Some(parse_micros(field, 1_000).ok_or(())?),
+                b'T' => match field {
+                    "DT" => {}
+                    "RR" => remote = true,
+                    "FD" | "FB" | "FE" | "BI" => {
+                        rprintln!("WARNING - CAN FD frame skipped: {}", line);
+                        return Ok(None);
+                    }
+                    _ => return Ok(None), // skip errors, status and other events
+                },
+                b'd' if !matches!(field, "Rx" | "Tx") => return Ok(None), // skip v1.1 events
+                b'I' => id = Some(parse_trc_id(field).ok_or(())?),
+                b'l' | b'L' => len = Some(field.parse::<u8>().map_err(|_| ())?),
+                _ => {} // number, bus, reserved and direction columns
+            }
+        }
+        let (Some(id), Some(len)) = (id, len) else {
+            return Err(());
+        };
+        if len > 8 {
+            return Err(());
+        }
+
+        let mut fields = fields.peekable();
+        let frame = if remote || fields.peek() == Some(&"RTR") {
+            Frame::new_remote(id, len)
+        } else {
+            let data = fields
+                .take(len as usize)
+                .map(|byte| u8::from_str_radix(byte, 16).ok())
+                .collect::<Option<Vec<u8, 8>>>()
+                .ok_or(())?;
+            if data.len() != len as usize {
+                return Err(());
+            }
+            Frame::new_data(
+                id,
+                Data::new(&data).expect("data contains at most 8 bytes."),
+            )
+        };
+
+        if let Some(micros) = micros {
+            self.timestamp = micros;
+        }
+        Ok(Some(frame))
+    }
+}
+
+impl<S: LogSource> Iterator for TrcLogsIterator<S> {
+    type Item = Frame;
+
+    /// Reads the next [`Frame`] from the log file.
+    ///
+    /// If any error is encountered while reading the file, [`None`] is returned and iteration ends.
+    /// Lines that can't be parsed are skipped, and recorded in [`bad_lines()`][Self::bad_lines()].
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(line) = self.lines.next_line() {
+            if let Some(comment) = line.trim_start().strip_prefix(';') {
+                self.parse_header(comment.trim());
+                continue;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match self.parse_line(&line) {
+                Ok(Some(frame)) => return Some(frame),
+                Ok(None) => {}
+                Err(()) => self.lines.reject(), // invalid frame
+            }
+        }
+        None
+    }
+}
+
+/// Parses a hexadecimal TRC identifier, identifiers of more than 4 digits being extended ones.
+///
+/// Returns [`None`] if `id` isn't a valid identifier.
+fn parse_trc_id(id: &str) -> Option<Id> {
+    if id.len() > 4 {
+        Some(Id::Extended(ExtendedId::new(
+            u32::from_str_radix(id, 16).ok()?,
+        )?))
+    } else {
+        Some(Id::Standard(StandardId::new(
+            u16::from_str_radix(id, 16).ok()?,
+        )?))
+    }
+}
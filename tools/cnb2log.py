@@ -0,0 +1,60 @@
+#!/usr/bin/env python3
+"""Converts CANary binary captures (`.cnb`) to the `can-utils` log format.
+
+Usage: cnb2log.py CAPTURE.CNB [OUTPUT.LOG]
+
+The log is written to the standard output if no output file is given.
+"""
+
+import struct
+import sys
+
+HEADER = struct.Struct("<4sBBHI4x16s")
+RECORD = struct.Struct("<QIBB2x8s")
+
+EXTENDED_FLAG = 0x01
+REMOTE_FLAG = 0x02
+TRANSMITTED_FLAG = 0x04
+
+
+def convert(capture, output):
+    header = capture.read(HEADER.size)
+    if len(header) != HEADER.size:
+        sys.exit("error: file too short for a CANary binary capture")
+    magic, version, flags, record_len, bitrate, device = HEADER.unpack(header)
+    if magic != b"CNB1" or version != 1 or record_len != RECORD.size:
+        sys.exit("error: not a CANary binary capture")
+
+    device = device.rstrip(b"\0").decode("ascii", "replace")
+    output.write(
+        f"# Frames captured by {device} - Bitrate: {bitrate // 1000:4} kbps, "
+        f"Silent: {'true' if flags & 0x01 else 'false'}\n"
+    )
+
+    while record := capture.read(RECORD.size):
+        if len(record) != RECORD.size:
+            sys.exit("error: truncated record at the end of the capture")
+        micros, can_id, flags, dlc, data = RECORD.unpack(record)
+
+        id_text = f"{can_id:08X}" if flags & EXTENDED_FLAG else f"{can_id:03X}"
+        payload = f"R{dlc}" if flags & REMOTE_FLAG else data[:dlc].hex().upper()
+        interface = "can0tx" if flags & TRANSMITTED_FLAG else "can0"
+        output.write(
+            f"({micros // 1_000_000:010}.{micros % 1_000_000:06}) "
+            f"{interface} {id_text}#{payload}\n"
+        )
+
+
+def main():
+    if len(sys.argv) not in (2, 3):
+        sys.exit(__doc__.strip())
+    with open(sys.argv[1], "rb") as capture:
+        if len(sys.argv) == 3:
+            with open(sys.argv[2], "w", encoding="utf-8") as output:
+                convert(capture, output)
+        else:
+            convert(capture, sys.stdout)
+
+
+if __name__ == "__main__":
+    main()